@@ -1,27 +1,70 @@
 use crate::app::config::App;
 use crate::app::config::AppCommon;
+use crate::app::config::OutputFormat;
+use crate::app::config::TransformOptions;
+use crate::app::watch;
 use anyhow::{Context, Result};
-use hogan::config::ConfigDir;
+use handlebars::Handlebars;
+use hogan::config::{ConfigDir, Environment};
+use hogan::coverage;
+use hogan::diff::{self, EnvironmentDiff};
 use hogan::error::HoganError;
-use hogan::template::TemplateDir;
+use hogan::history;
+use hogan::lint::{self, LintIssue};
+use hogan::manifest::{content_hash, Manifest, ManifestEntry, MANIFEST_FILE};
+use hogan::template::{
+    file_mode, Bundle, InvalidTemplate, MissingValue, OutputPattern, RenderStatus, Rendered,
+    Template, TemplateDir,
+};
+use hogan::transform::PARTIALS_DIR;
+use hogan::vars;
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
 use regex::Regex;
-use std::fs::File;
-use std::fs::OpenOptions;
-use std::io::ErrorKind::AlreadyExists;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::ErrorKind::{AlreadyExists, NotFound};
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use tempfile::NamedTempFile;
 
 pub fn cli(
     templates_path: PathBuf,
     environments_regex: Regex,
     templates_regex: Regex,
     common: AppCommon,
-    ignore_existing: bool,
+    options: TransformOptions,
 ) -> Result<()> {
-    let handlebars = hogan::transform::handlebars(common.strict);
+    if options.watch {
+        return watch::watch(
+            templates_path,
+            environments_regex,
+            templates_regex,
+            common,
+            options,
+        );
+    }
+
+    let handlebars = build_handlebars(
+        common.strict || options.report_missing,
+        &templates_path,
+        options.partials_path.as_deref(),
+    )?;
 
-    let template_dir = TemplateDir::new(templates_path)?;
-    let mut templates = template_dir.find(templates_regex);
+    let output_root = options
+        .output_dir
+        .clone()
+        .unwrap_or_else(|| templates_path.clone());
+    // Invalid templates are reported as they're found, but still fail the run once the valid
+    // ones have been handled
+    let (templates, invalid) = find_templates(
+        &templates_path,
+        templates_regex.clone(),
+        options.output_pattern.as_ref(),
+        options.escape_by_extension,
+    )?;
+    report_invalid(&invalid);
     println!("Loaded {} template file(s)", templates.len());
 
     let config_dir = ConfigDir::new(
@@ -31,50 +74,884 @@ pub fn cli(
         common.native_fetch,
         common.native_clone,
     )?;
-    let environments = config_dir.find(App::config_regex(&environments_regex)?);
+    let environments_filter = App::config_regex(&environments_regex)?;
+    let environments = config_dir.find(environments_filter.clone());
     println!("Loaded {} config file(s)", environments.len());
+    let config_sha = config_dir.head_sha();
+
+    let mut statuses = Vec::new();
+    let mut bundle = options.bundle_path.as_ref().map(|_| Bundle::new());
+    let mut generated = Vec::new();
 
-    for environment in environments {
-        println!("Updating templates for {}", environment.environment);
+    let pairs = environments
+        .iter()
+        .flat_map(|e| {
+            templates
+                .iter()
+                .filter(move |t| t.applies_to(e))
+                .map(move |t| (e, t))
+        })
+        .collect::<Vec<_>>();
+
+    // Rendering happens on the worker pool, the results are then handled in order so the output
+    // is the same regardless of how many jobs are used
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(options.jobs.unwrap_or(0))
+        .build()?;
+    if options.report_missing {
+        return report_missing(
+            &handlebars,
+            &pool,
+            &pairs,
+            config_sha.as_deref(),
+            &templates_path,
+            invalid.len(),
+        );
+    }
+    let results = pool.install(|| {
+        pairs
+            .par_iter()
+            .map(|(environment, template)| {
+                debug!("Transforming {:?}", template.path);
+                render_output(
+                    &handlebars,
+                    template,
+                    environment,
+                    config_sha.as_deref(),
+                    options.output_dir.as_deref(),
+                )
+            })
+            .collect::<Vec<_>>()
+    });
+    let mut results = pairs.into_iter().zip(results).peekable();
+    let mut failures = Vec::new();
 
-        for template in &mut templates {
-            debug!("Transforming {:?}", template.path);
+    for environment in &environments {
+        if options.check {
+            println!("Checking templates for {}", environment.environment);
+        } else {
+            println!("Updating templates for {}", environment.environment);
+        }
 
-            let rendered = match template.render(&handlebars, &environment) {
+        while let Some(((_, template), result)) =
+            results.next_if(|((e, _), _)| e.environment == environment.environment)
+        {
+            let rendered = match result {
                 Ok(rendered) => rendered,
                 Err(err) => {
                     error!(
                         "There was an error rendering environment {} with template {:?}. Error: {:?}",
                         environment.environment, template.path, err
                     );
-                    return Err(err);
+                    failures.push(format!(
+                        "{} for {}: {:#}",
+                        template.path.display(),
+                        environment.environment,
+                        err
+                    ));
+                    continue;
                 }
             };
             trace!("Rendered: {:?}", rendered.contents);
 
-            if ignore_existing {
-                if let Err(e) = match OpenOptions::new()
-                    .write(true)
-                    .create_new(true)
-                    .open(&rendered.path)
-                {
-                    Ok(ref mut f) => f.write_all(&rendered.contents),
-                    Err(ref e) if e.kind() == AlreadyExists => {
-                        println!("Skipping {:?} - config already exists.", rendered.path);
-                        trace!("Skipping {:?} - config already exists.", rendered.path);
-                        Ok(())
-                    }
-                    Err(e) => Err(e),
-                } {
-                    return Err(HoganError::UnknownError {
-                        msg: format!("Error transforming {:?} due to {:?}", rendered.path, e),
-                    })
-                    .with_context(|| "Error while ignoring existing");
+            if let Some(ref mut bundle) = bundle {
+                bundle.add(
+                    &template.relative_output_path(environment),
+                    &rendered.contents,
+                )?;
+                continue;
+            }
+
+            let skip_existing = options.ignore_existing && rendered.path.exists();
+
+            if options.diff && !skip_existing {
+                if let Some(diff) = rendered.unified_diff()? {
+                    println!("Template {}", template.path.display());
+                    print!("{}", diff);
                 }
+            }
+
+            if options.check {
+                let status = if skip_existing {
+                    RenderStatus::Unchanged
+                } else {
+                    rendered.status()?
+                };
+                println!("  {:<9} {}", status, rendered.path.display());
+                statuses.push(status);
             } else {
-                File::create(&rendered.path)?
-                    .write_all(&rendered.contents)
-                    .with_context(|| format!("Error transforming {:?}", rendered.path))?;
+                // A file kept by --ignore-existing is recorded with the contents left on disk
+                let hash = if write_rendered(&rendered, options.ignore_existing)? {
+                    content_hash(&rendered.contents)
+                } else {
+                    content_hash(
+                        &fs::read(&rendered.path)
+                            .with_context(|| format!("Error reading {:?}", rendered.path))?,
+                    )
+                };
+                generated.push(ManifestEntry {
+                    path: template.relative_output_path(environment),
+                    template: template
+                        .path
+                        .strip_prefix(&templates_path)
+                        .unwrap_or(&template.path)
+                        .to_owned(),
+                    environment: environment.environment.to_owned(),
+                    config_sha: config_sha.clone(),
+                    hash,
+                });
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        eprintln!("Failed to render {} file(s):", failures.len());
+        for failure in &failures {
+            eprintln!("  {}", failure);
+        }
+    }
+
+    let bundle_written = bundle.is_some();
+    if let (Some(bundle), Some(bundle_path), true) = (
+        bundle,
+        options.bundle_path,
+        failures.is_empty() && invalid.is_empty(),
+    ) {
+        let count = bundle.len();
+        fs::write(&bundle_path, bundle.finish()?)
+            .with_context(|| format!("Error writing bundle {:?}", bundle_path))?;
+        println!("Wrote {} file(s) to {:?}", count, bundle_path);
+    }
+
+    if !options.check && !bundle_written {
+        // Previous outputs are only stale if this run could have generated them. The outputs of
+        // templates which failed to load are kept until the template is fixed or removed
+        let invalid_paths = invalid
+            .iter()
+            .map(|t| t.path.strip_prefix(&templates_path).unwrap_or(&t.path))
+            .collect::<BTreeSet<_>>();
+        let in_scope = |entry: &ManifestEntry| {
+            let template_name = entry.template.file_name().and_then(|f| f.to_str());
+            !entry.template.starts_with(PARTIALS_DIR)
+                && !invalid_paths.contains(entry.template.as_path())
+                && template_name.is_some_and(|name| templates_regex.is_match(name))
+                && environments_filter.is_match(&format!("config.{}.json", entry.environment))
+        };
+        // Outputs of failed renders were not generated, but must not be pruned
+        update_manifest(
+            &output_root,
+            generated,
+            in_scope,
+            options.prune && failures.is_empty(),
+        )?;
+    }
+
+    let mut out_of_date = 0;
+    if options.check {
+        let count = |status| statuses.iter().filter(|s| **s == status).count();
+        out_of_date = count(RenderStatus::Created) + count(RenderStatus::Changed);
+        println!(
+            "{} new, {} changed and {} unchanged file(s)",
+            count(RenderStatus::Created),
+            count(RenderStatus::Changed),
+            count(RenderStatus::Unchanged)
+        );
+    }
+
+    if !failures.is_empty() || !invalid.is_empty() {
+        Err(HoganError::RenderFailed {
+            msg: failure_message(failures.len(), invalid.len()),
+        }
+        .into())
+    } else if out_of_date > 0 {
+        Err(HoganError::CheckFailed {
+            msg: format!("{} rendered file(s) are out of date", out_of_date),
+        }
+        .into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Collects the values missing from the configs for every template and environment, printing
+/// them as a table with the environments missing each value
+fn report_missing(
+    handlebars: &Handlebars,
+    pool: &ThreadPool,
+    pairs: &[(&Environment, &Template)],
+    sha: Option<&str>,
+    templates_path: &Path,
+    invalid: usize,
+) -> Result<()> {
+    let results = pool.install(|| {
+        pairs
+            .par_iter()
+            .map(|(environment, template)| template.find_missing(handlebars, environment, sha))
+            .collect::<Vec<_>>()
+    });
+
+    let mut missing: BTreeMap<(String, MissingValue), Vec<&str>> = BTreeMap::new();
+    let mut failures = Vec::new();
+    for ((environment, template), result) in pairs.iter().zip(results) {
+        let template_name = template
+            .path
+            .strip_prefix(templates_path)
+            .unwrap_or(&template.path)
+            .display()
+            .to_string();
+        match result {
+            Ok(values) => {
+                for value in values {
+                    missing
+                        .entry((template_name.clone(), value))
+                        .or_default()
+                        .push(&environment.environment);
+                }
+            }
+            Err(err) => failures.push(format!(
+                "{} for {}: {:#}",
+                template.path.display(),
+                environment.environment,
+                err
+            )),
+        }
+    }
+
+    let rows = missing
+        .iter()
+        .map(|((template, value), environments)| {
+            vec![
+                match value.partial {
+                    Some(ref partial) => format!("{} (partial {})", template, partial),
+                    None => template.to_owned(),
+                },
+                value.line.to_string(),
+                value.column.to_string(),
+                value.path.to_owned(),
+                environments.join(", "),
+            ]
+        })
+        .collect::<Vec<_>>();
+    if rows.is_empty() {
+        println!("No missing values");
+    } else {
+        print_table(
+            &["Template", "Line", "Column", "Missing", "Environments"],
+            &rows,
+        );
+    }
+
+    if !failures.is_empty() {
+        eprintln!("Failed to render {} file(s):", failures.len());
+        for failure in &failures {
+            eprintln!("  {}", failure);
+        }
+    }
+
+    if missing.is_empty() && failures.is_empty() && invalid == 0 {
+        Ok(())
+    } else {
+        let files = missing
+            .iter()
+            .flat_map(|((template, _), environments)| {
+                environments.iter().map(move |e| (template, *e))
+            })
+            .collect::<BTreeSet<_>>();
+        let mut msg = format!(
+            "{} missing value(s) in {} file(s)",
+            missing.values().map(Vec::len).sum::<usize>(),
+            files.len()
+        );
+        // Finding a missing value stops at the first one inside a partial
+        let incomplete = missing
+            .iter()
+            .filter(|((_, value), _)| value.partial.is_some())
+            .flat_map(|((template, _), environments)| {
+                environments.iter().map(move |e| (template, *e))
+            })
+            .collect::<BTreeSet<_>>();
+        if !incomplete.is_empty() {
+            msg.push_str(&format!(
+                ", results for {} file(s) are incomplete as only the first missing value in a partial is found",
+                incomplete.len()
+            ));
+        }
+        if !failures.is_empty() || invalid > 0 {
+            msg.push_str(" and ");
+            msg.push_str(&failure_message(failures.len(), invalid));
+        }
+        Err(HoganError::RenderFailed { msg }.into())
+    }
+}
+
+/// Describes the renders that failed and the templates that could not be loaded
+fn failure_message(failures: usize, invalid: usize) -> String {
+    let mut problems = Vec::new();
+    if failures > 0 {
+        problems.push(format!("{} file(s) could not be rendered", failures));
+    }
+    if invalid > 0 {
+        problems.push(format!("{} template(s) have invalid front matter", invalid));
+    }
+    problems.join(" and ")
+}
+
+/// Prints the rows in aligned columns below the header. Numeric columns are right aligned
+fn print_table(header: &[&str], rows: &[Vec<String>]) {
+    let header = header.iter().map(|h| h.to_string()).collect::<Vec<_>>();
+    let columns = (0..header.len())
+        .map(|i| {
+            let width = Some(&header)
+                .into_iter()
+                .chain(rows)
+                .map(|row| row[i].chars().count())
+                .max()
+                .unwrap_or(0);
+            let numeric = rows.iter().all(|row| row[i].parse::<usize>().is_ok());
+            (width, numeric)
+        })
+        .collect::<Vec<_>>();
+
+    for row in Some(&header).into_iter().chain(rows) {
+        let line = row
+            .iter()
+            .zip(&columns)
+            .enumerate()
+            .map(|(i, (cell, (width, numeric)))| {
+                if *numeric {
+                    format!("{:>width$}", cell, width = width)
+                } else if i + 1 < row.len() {
+                    format!("{:<width$}", cell, width = width)
+                } else {
+                    cell.to_owned()
+                }
+            })
+            .collect::<Vec<_>>();
+        println!("{}", line.join("  ").trim_end());
+    }
+}
+
+/// Creates the handlebars registry with the partials found in the templates' _partials
+/// directory and the configured partials directory
+pub(crate) fn build_handlebars<'a>(
+    strict: bool,
+    templates_path: &Path,
+    partials_path: Option<&Path>,
+) -> Result<Handlebars<'a>> {
+    let mut handlebars = hogan::transform::handlebars(strict);
+
+    let default_partials_path = templates_path.join(PARTIALS_DIR);
+    for partials_path in Some(default_partials_path.as_path())
+        .filter(|p| p.is_dir())
+        .into_iter()
+        .chain(partials_path)
+    {
+        let count = hogan::transform::register_partials_dir(&mut handlebars, partials_path)?;
+        info!("Loaded {} partial(s) from {:?}", count, partials_path);
+    }
+
+    Ok(handlebars)
+}
+
+/// Finds the templates to render, excluding partials. Templates with invalid front matter are
+/// reported on stderr and left out
+pub(crate) fn load_templates(
+    templates_path: &Path,
+    templates_regex: Regex,
+    output_pattern: Option<&OutputPattern>,
+    escape_by_extension: bool,
+) -> Result<Vec<Template>> {
+    let (templates, invalid) = find_templates(
+        templates_path,
+        templates_regex,
+        output_pattern,
+        escape_by_extension,
+    )?;
+    report_invalid(&invalid);
+
+    Ok(templates)
+}
+
+fn report_invalid(invalid: &[InvalidTemplate]) {
+    for template in invalid {
+        eprintln!("Skipping {}: {:#}", template.path.display(), template.error);
+    }
+}
+
+/// Finds the templates to render, excluding partials, and separately those with invalid front
+/// matter
+fn find_templates(
+    templates_path: &Path,
+    templates_regex: Regex,
+    output_pattern: Option<&OutputPattern>,
+    escape_by_extension: bool,
+) -> Result<(Vec<Template>, Vec<InvalidTemplate>)> {
+    let default_partials_path = templates_path.join(PARTIALS_DIR);
+    let (templates, invalid) = TemplateDir::new(templates_path.to_owned())?.find(templates_regex);
+    let mut templates = templates
+        .into_iter()
+        .filter(|t| !t.path.starts_with(&default_partials_path))
+        .collect::<Vec<_>>();
+    let invalid = invalid
+        .into_iter()
+        .filter(|t| !t.path.starts_with(&default_partials_path))
+        .collect::<Vec<_>>();
+
+    // An output pattern in a template's front matter takes precedence over the command line
+    for template in templates.iter_mut().filter(|t| t.output_pattern.is_none()) {
+        template.output_pattern = output_pattern.cloned();
+    }
+    if escape_by_extension {
+        for template in templates.iter_mut() {
+            template.escape = Some(template.escape_for_extension());
+        }
+    }
+
+    Ok((templates, invalid))
+}
+
+/// Renders a template for an environment, rebasing the output path on the output directory
+pub(crate) fn render_output(
+    handlebars: &Handlebars,
+    template: &Template,
+    environment: &Environment,
+    sha: Option<&str>,
+    output_dir: Option<&Path>,
+) -> Result<Rendered> {
+    let mut rendered = template.render(handlebars, environment, sha)?;
+    if let Some(output_dir) = output_dir {
+        rendered.path = output_dir.join(template.relative_output_path(environment));
+    }
+    Ok(rendered)
+}
+
+fn update_manifest(
+    output_root: &Path,
+    generated: Vec<ManifestEntry>,
+    in_scope: impl Fn(&ManifestEntry) -> bool,
+    prune: bool,
+) -> Result<()> {
+    let manifest_path = output_root.join(MANIFEST_FILE);
+    let previous = Manifest::load(&manifest_path)?;
+    let (mut manifest, stale) = Manifest::update(previous, generated, in_scope);
+
+    if prune {
+        for entry in &stale {
+            let path = output_root.join(&entry.path);
+            match fs::remove_file(&path) {
+                Ok(_) => println!("Pruned {:?}", path),
+                Err(ref e) if e.kind() == NotFound => {}
+                Err(e) => {
+                    return Err(e).with_context(|| format!("Error pruning {:?}", path));
+                }
+            }
+        }
+    } else if !stale.is_empty() {
+        println!(
+            "{} previously generated file(s) are no longer generated. Run with --prune to delete them",
+            stale.len()
+        );
+        manifest.entries.extend(stale);
+        manifest.entries.sort_by(|a, b| a.path.cmp(&b.path));
+    }
+
+    manifest.save(&manifest_path)
+}
+
+/// Writes through a temp file in the output directory which is renamed into place, so a failure
+/// never leaves a partially written file. Files whose contents are unchanged are not rewritten.
+/// Returns false when an existing file was kept because of ignore_existing
+pub(crate) fn write_rendered(rendered: &Rendered, ignore_existing: bool) -> Result<bool> {
+    let parent = rendered
+        .path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    fs::create_dir_all(parent)
+        .with_context(|| format!("Error creating output directory {:?}", parent))?;
+
+    if ignore_existing && rendered.path.exists() {
+        println!("Skipping {:?} - config already exists.", rendered.path);
+        trace!("Skipping {:?} - config already exists.", rendered.path);
+        return Ok(false);
+    }
+
+    // Without a mode from the template keep the permissions of the file being replaced
+    let mode = rendered.mode.or_else(|| file_mode(&rendered.path));
+
+    if rendered.status()? == RenderStatus::Unchanged {
+        debug!("Skipping {:?} - contents are unchanged", rendered.path);
+        set_mode(&rendered.path, mode)
+            .with_context(|| format!("Error setting the mode of {:?}", rendered.path))?;
+        return Ok(true);
+    }
+
+    let mut temp_file = NamedTempFile::new_in(parent)
+        .with_context(|| format!("Error creating a temp file in {:?}", parent))?;
+    temp_file
+        .write_all(&rendered.contents)
+        .and_then(|_| set_mode(temp_file.path(), mode.or(Some(DEFAULT_MODE))))
+        .with_context(|| format!("Error transforming {:?}", rendered.path))?;
+
+    if ignore_existing {
+        // Another process may have created the file since it was checked
+        if let Err(e) = temp_file.persist_noclobber(&rendered.path) {
+            if e.error.kind() == AlreadyExists {
+                println!("Skipping {:?} - config already exists.", rendered.path);
+                return Ok(false);
+            }
+            return Err(HoganError::UnknownError {
+                msg: format!("Error transforming {:?} due to {:?}", rendered.path, e),
+            })
+            .with_context(|| "Error while ignoring existing");
+        }
+    } else {
+        temp_file
+            .persist(&rendered.path)
+            .with_context(|| format!("Error transforming {:?}", rendered.path))?;
+    }
+
+    Ok(true)
+}
+
+/// Mode of newly created files when the template's permissions are unknown
+const DEFAULT_MODE: u32 = 0o644;
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: Option<u32>) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    match mode {
+        Some(mode) if file_mode(path) != Some(mode) => {
+            fs::set_permissions(path, fs::Permissions::from_mode(mode))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: Option<u32>) -> std::io::Result<()> {
+    Ok(())
+}
+
+pub fn diff(
+    common: AppCommon,
+    from: String,
+    to: String,
+    environments_regex: Regex,
+    format: OutputFormat,
+) -> Result<()> {
+    let config_dir = ConfigDir::new(
+        common.configs_url,
+        &common.ssh_key,
+        common.native_git,
+        common.native_fetch,
+        common.native_clone,
+    )?;
+    let filter = App::config_regex(&environments_regex)?;
+
+    let from_environments = config_dir.find_at_revision(&from, filter.clone())?;
+    let to_environments = config_dir.find_at_revision(&to, filter)?;
+    debug!(
+        "Comparing {} environment(s) at {} to {} environment(s) at {}",
+        from_environments.len(),
+        from,
+        to_environments.len(),
+        to
+    );
+
+    let diffs = diff::diff_environments(&from_environments, &to_environments);
+    print_diffs(&diffs, format)
+}
+
+pub fn compare(
+    common: AppCommon,
+    from: String,
+    to: String,
+    sha: Option<String>,
+    collapse_env_names: bool,
+    format: OutputFormat,
+) -> Result<()> {
+    let config_dir = ConfigDir::new(
+        common.configs_url,
+        &common.ssh_key,
+        common.native_git,
+        common.native_fetch,
+        common.native_clone,
+    )?;
+    let filter = App::config_regex(&App::parse_regex(&format!(
+        "({}|{})",
+        regex::escape(&from),
+        regex::escape(&to)
+    ))?)?;
+
+    let environments = match sha {
+        Some(ref sha) => config_dir.find_at_revision(sha, filter)?,
+        None => config_dir.find(filter),
+    };
+    let find_environment = |name: &str| {
+        environments
+            .iter()
+            .find(|e| e.environment.eq_ignore_ascii_case(name))
+            .ok_or_else(|| HoganError::UnknownEnvironment {
+                sha: sha
+                    .clone()
+                    .unwrap_or_else(|| "the configs directory".to_string()),
+                env: name.to_owned(),
+            })
+    };
+
+    let comparison = diff::compare_environments(
+        find_environment(&from)?,
+        find_environment(&to)?,
+        collapse_env_names,
+    );
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&comparison)?),
+        OutputFormat::Text => print!("{}", comparison),
+    }
+
+    Ok(())
+}
+
+pub fn log(
+    common: AppCommon,
+    environment: String,
+    path: String,
+    from: String,
+    limit: Option<usize>,
+    format: OutputFormat,
+) -> Result<()> {
+    let config_dir = ConfigDir::new(
+        common.configs_url,
+        &common.ssh_key,
+        common.native_git,
+        common.native_fetch,
+        common.native_clone,
+    )?;
+    let filter = App::config_regex(&App::parse_regex(&regex::escape(&environment))?)?;
+
+    let history = history::value_history(&config_dir, &from, &environment, &path, &filter, limit)?;
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&history)?),
+        OutputFormat::Text => print!("{}", history),
+    }
+
+    Ok(())
+}
+
+pub fn vars(
+    common: AppCommon,
+    template_path: PathBuf,
+    templates_path: PathBuf,
+    partials_path: Option<PathBuf>,
+    environments_regex: Regex,
+    format: OutputFormat,
+) -> Result<()> {
+    let handlebars = build_handlebars(false, &templates_path, partials_path.as_deref())?;
+    let source = fs::read_to_string(&template_path)
+        .with_context(|| format!("Error reading template {:?}", template_path))?;
+    let template = Template::new(&templates_path, template_path, &source)?;
+
+    let config_dir = ConfigDir::new(
+        common.configs_url,
+        &common.ssh_key,
+        common.native_git,
+        common.native_fetch,
+        common.native_clone,
+    )?;
+    let environments = config_dir
+        .find(App::config_regex(&environments_regex)?)
+        .into_iter()
+        .filter(|e| template.applies_to(e))
+        .collect::<Vec<_>>();
+
+    let checks = vars::check(vars::references(&handlebars, &template)?, &environments);
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&checks)?),
+        OutputFormat::Text => {
+            let rows = checks
+                .iter()
+                .map(|c| {
+                    let missing_from = if c.missing_from.is_empty() {
+                        String::new()
+                    } else if c.reference.optional {
+                        format!("{} (optional)", c.missing_from.join(", "))
+                    } else {
+                        c.missing_from.join(", ")
+                    };
+                    vec![
+                        c.reference.path.to_owned(),
+                        c.reference.line.to_string(),
+                        c.reference.column.to_string(),
+                        c.reference.partial.clone().unwrap_or_default(),
+                        missing_from,
+                    ]
+                })
+                .collect::<Vec<_>>();
+            print_table(
+                &["Path", "Line", "Column", "Partial", "Missing from"],
+                &rows,
+            );
+            println!(
+                "{} path(s) referenced, checked against {} environment(s)",
+                checks.len(),
+                environments.len()
+            );
+        }
+    }
+
+    let missing = checks.iter().filter(|c| c.is_missing()).count();
+    if missing > 0 {
+        Err(HoganError::CheckFailed {
+            msg: format!(
+                "{} referenced path(s) are missing from at least one environment",
+                missing
+            ),
+        }
+        .into())
+    } else {
+        Ok(())
+    }
+}
+
+pub fn coverage(
+    common: AppCommon,
+    templates_path: PathBuf,
+    templates_regex: Regex,
+    partials_path: Option<PathBuf>,
+    environments_regex: Regex,
+    unused: bool,
+    format: OutputFormat,
+) -> Result<()> {
+    let handlebars = build_handlebars(false, &templates_path, partials_path.as_deref())?;
+    let templates = load_templates(&templates_path, templates_regex, None, false)?
+        .into_iter()
+        .map(|template| {
+            let references = vars::references(&handlebars, &template)?;
+            Ok((template, references))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let config_dir = ConfigDir::new(
+        common.configs_url,
+        &common.ssh_key,
+        common.native_git,
+        common.native_fetch,
+        common.native_clone,
+    )?;
+    let environments = config_dir.find_with_layers(App::config_regex(&environments_regex)?);
+
+    let keys = coverage::coverage(&environments, &templates);
+    let unused_count = keys.iter().filter(|k| !k.used).count();
+    let listed = keys
+        .iter()
+        .filter(|k| !unused || !k.used)
+        .collect::<Vec<_>>();
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&listed)?),
+        OutputFormat::Text => {
+            let rows = listed
+                .iter()
+                .map(|k| {
+                    vec![
+                        k.path.to_owned(),
+                        if k.used { "yes" } else { "no" }.to_string(),
+                        if k.global { "yes" } else { "" }.to_string(),
+                        k.environment_types.join(", "),
+                        k.environments.join(", "),
+                    ]
+                })
+                .collect::<Vec<_>>();
+            print_table(&["Key", "Used", "Global", "Types", "Environments"], &rows);
+            println!(
+                "{} of {} key(s) are not used by any of {} template(s) across {} environment(s)",
+                unused_count,
+                keys.len(),
+                templates.len(),
+                environments.len()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct TemplateIssues {
+    template: PathBuf,
+    issues: Vec<LintIssue>,
+}
+
+pub fn validate(
+    templates_path: PathBuf,
+    templates_regex: Regex,
+    partials_path: Option<PathBuf>,
+    format: OutputFormat,
+) -> Result<()> {
+    let handlebars = build_handlebars(false, &templates_path, partials_path.as_deref())?;
+    let (templates, invalid) = find_templates(&templates_path, templates_regex, None, false)?;
+
+    let results = invalid
+        .iter()
+        .map(|template| TemplateIssues {
+            template: template.path.clone(),
+            issues: vec![LintIssue {
+                line: None,
+                column: None,
+                message: format!("{:#}", template.error),
+            }],
+        })
+        .chain(templates.iter().map(|template| TemplateIssues {
+            template: template.path.clone(),
+            issues: lint::lint(&handlebars, template),
+        }))
+        .filter(|t| !t.issues.is_empty())
+        .collect::<Vec<_>>();
+    let count = results.iter().map(|t| t.issues.len()).sum::<usize>();
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&results)?),
+        OutputFormat::Text => {
+            for result in &results {
+                for issue in &result.issues {
+                    let separator = if issue.line.is_some() { ":" } else { ": " };
+                    println!("{}{}{}", result.template.display(), separator, issue);
+                }
+            }
+            println!(
+                "Validated {} template(s), found {} problem(s)",
+                templates.len() + invalid.len(),
+                count
+            );
+        }
+    }
+
+    if count > 0 {
+        Err(HoganError::CheckFailed {
+            msg: format!(
+                "{} problem(s) found in {} template(s)",
+                count,
+                results.len()
+            ),
+        }
+        .into())
+    } else {
+        Ok(())
+    }
+}
+
+fn print_diffs(diffs: &[EnvironmentDiff], format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(diffs)?),
+        OutputFormat::Text => {
+            for diff in diffs {
+                print!("{}", diff);
             }
         }
     }
@@ -93,17 +970,27 @@ mod tests {
     use self::assert_cmd::prelude::*;
     use self::fs_extra::dir;
     use self::predicates::prelude::*;
+    use crate::test_repo::{commit_all, init_config_repo};
+    use hogan::manifest::{content_hash, Manifest, MANIFEST_FILE};
     use std::io::Write;
     use std::path::Path;
     use std::process::Command;
 
+    fn update_config(configs_path: &Path, env: &str, from: &str, to: &str) {
+        let config_path = configs_path.join(format!("config.{}.json", env));
+        let updated = std::fs::read_to_string(&config_path)
+            .unwrap()
+            .replace(from, to);
+        std::fs::write(&config_path, updated).unwrap();
+    }
+
     #[cfg(not(all(target_env = "msvc", target_arch = "x86_64")))]
     #[test]
     fn test_transform() {
         let temp_dir = tempfile::tempdir().unwrap();
 
         fs_extra::copy_items(
-            &vec!["tests/fixtures/projects/templates"],
+            &["tests/fixtures/projects/templates"],
             temp_dir.path(),
             &dir::CopyOptions::new(),
         )
@@ -113,7 +1000,7 @@ mod tests {
 
         let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
 
-        let cmd = cmd.args(&[
+        let cmd = cmd.args([
             "transform",
             "--configs",
             "tests/fixtures/configs",
@@ -123,27 +1010,9 @@ mod tests {
 
         cmd.assert().success();
 
-        cmd.assert().stdout(
-            predicate::str::contains(format!(r#"Finding Files: {:?}"#, templates_path)).from_utf8(),
-        );
-
-        cmd.assert().stdout(
-            predicate::str::contains(
-                r"regex: /^[^.]*(\w+\.)*template([-.].+)?\.(config|ya?ml|properties)/",
-            )
-            .from_utf8(),
-        );
-
         cmd.assert()
             .stdout(predicate::str::contains("Loaded 6 template file(s)").from_utf8());
 
-        cmd.assert().stdout(
-            predicate::str::contains(r#"Finding Files: "tests/fixtures/configs""#).from_utf8(),
-        );
-
-        cmd.assert()
-            .stdout(predicate::str::contains(r#"regex: /config\..+\.json$/"#).from_utf8());
-
         cmd.assert()
             .stdout(predicate::str::contains("Loaded 4 config file(s)").from_utf8());
 
@@ -155,14 +1024,14 @@ mod tests {
         }
 
         assert!(!dir_diff::is_different(
-            &templates_path.join("project-1"),
-            &Path::new("tests/fixtures/projects/rendered/project-1")
+            templates_path.join("project-1"),
+            Path::new("tests/fixtures/projects/rendered/project-1")
         )
         .unwrap());
 
         assert!(!dir_diff::is_different(
-            &templates_path.join("project-2"),
-            &Path::new("tests/fixtures/projects/rendered/project-2")
+            templates_path.join("project-2"),
+            Path::new("tests/fixtures/projects/rendered/project-2")
         )
         .unwrap());
     }
@@ -173,7 +1042,7 @@ mod tests {
         let temp_dir = tempfile::tempdir().unwrap();
 
         fs_extra::copy_items(
-            &vec!["tests/fixtures/projects/templates"],
+            &["tests/fixtures/projects/templates"],
             temp_dir.path(),
             &dir::CopyOptions::new(),
         )
@@ -185,6 +1054,7 @@ mod tests {
         if let Ok(ref mut f) = std::fs::OpenOptions::new()
             .write(true)
             .create(true)
+            .truncate(true)
             .open(&ignore_path)
         {
             f.write_all(b"Hamburger.")
@@ -192,7 +1062,7 @@ mod tests {
         }
 
         let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
-        let cmd = cmd.args(&[
+        let cmd = cmd.args([
             "transform",
             "--configs",
             "tests/fixtures/configs",
@@ -209,10 +1079,19 @@ mod tests {
             std::fs::read_to_string(&ignore_path).expect("Failed to read test file for ignore.");
         assert!(data2 == "Hamburger.");
 
+        // The manifest records the file that was kept, not the contents that were skipped
+        let manifest = Manifest::load(&templates_path.join(MANIFEST_FILE)).unwrap();
+        let kept = manifest
+            .entries
+            .iter()
+            .find(|e| e.path == Path::new("project-1/Web.EMPTY.config"))
+            .unwrap();
+        assert_eq!(kept.hash, content_hash(b"Hamburger."));
+
         // after running the command again without the ignore flag
         // assert that the configs now match those in the rendered directory
         let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
-        let cmd = cmd.args(&[
+        let cmd = cmd.args([
             "transform",
             "--configs",
             "tests/fixtures/configs",
@@ -222,15 +1101,1313 @@ mod tests {
         cmd.assert().success();
 
         assert!(!dir_diff::is_different(
-            &templates_path.join("project-1"),
-            &Path::new("tests/fixtures/projects/rendered/project-1")
+            templates_path.join("project-1"),
+            Path::new("tests/fixtures/projects/rendered/project-1")
         )
         .unwrap());
 
         assert!(!dir_diff::is_different(
-            &templates_path.join("project-2"),
-            &Path::new("tests/fixtures/projects/rendered/project-2")
+            templates_path.join("project-2"),
+            Path::new("tests/fixtures/projects/rendered/project-2")
         )
         .unwrap());
     }
+
+    #[cfg(not(all(target_env = "msvc", target_arch = "x86_64")))]
+    #[test]
+    fn test_diff() {
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        let repo = init_config_repo(temp_dir.path());
+        let configs_path = temp_dir.path().join("configs");
+        let from = repo
+            .head()
+            .unwrap()
+            .peel_to_commit()
+            .unwrap()
+            .id()
+            .to_string();
+
+        update_config(
+            &configs_path,
+            "TEST",
+            r#""Key": "TEST""#,
+            r#""Key": "UPDATED""#,
+        );
+        let to = commit_all(&repo, "Update TEST");
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let cmd = cmd.args([
+            "diff",
+            "--configs",
+            configs_path.to_str().unwrap(),
+            "--from",
+            &from,
+            "--to",
+            &to,
+            "-e",
+            "TEST2?",
+        ]);
+
+        cmd.assert().success();
+        cmd.assert().stdout(
+            predicate::str::contains(
+                "TEST: 1 change(s)\n  ~ Region.Key: \"TEST\" -> \"UPDATED\"\nTEST2: no changes",
+            )
+            .from_utf8(),
+        );
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let cmd = cmd.args([
+            "diff",
+            "--configs",
+            configs_path.to_str().unwrap(),
+            "--from",
+            &from,
+            "--to",
+            &to,
+            "-e",
+            "TEST",
+            "--format",
+            "json",
+        ]);
+
+        let output = cmd.output().unwrap();
+        let diffs: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(
+            diffs,
+            serde_json::json!([{
+                "Environment": "TEST",
+                "Changes": [{
+                    "Type": "Changed",
+                    "Path": "Region.Key",
+                    "From": "TEST",
+                    "To": "UPDATED"
+                }]
+            }])
+        );
+    }
+
+    #[cfg(not(all(target_env = "msvc", target_arch = "x86_64")))]
+    #[test]
+    fn test_compare() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let cmd = cmd.args([
+            "compare",
+            "--configs",
+            "tests/fixtures/configs",
+            "TEST",
+            "ENVTYPE",
+        ]);
+
+        cmd.assert().success();
+        cmd.assert().stdout(
+            predicate::str::contains(
+                "TEST -> ENVTYPE: 2 difference(s)\n  ~ Region.Key: \"TEST\" -> \"ENVTYPE\"\n  + EnvironmentType: \"alpha\"\n",
+            )
+            .from_utf8(),
+        );
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let cmd = cmd.args([
+            "compare",
+            "--configs",
+            "tests/fixtures/configs",
+            "TEST",
+            "ENVTYPE",
+            "--collapse-env-names",
+        ]);
+
+        cmd.assert().success();
+        cmd.assert().stdout(
+            predicate::str::contains(
+                "TEST -> ENVTYPE: 1 difference(s)\n  + EnvironmentType: \"alpha\"\n  (1 difference(s) only in the environment name collapsed)",
+            )
+            .from_utf8(),
+        );
+
+        // Environment names are matched case-insensitively, like the environment filter
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let cmd = cmd.args([
+            "compare",
+            "--configs",
+            "tests/fixtures/configs",
+            "test",
+            "envtype",
+        ]);
+
+        cmd.assert().success();
+        cmd.assert()
+            .stdout(predicate::str::contains("TEST -> ENVTYPE: 2 difference(s)").from_utf8());
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let cmd = cmd.args([
+            "compare",
+            "--configs",
+            "tests/fixtures/configs",
+            "TEST",
+            "MISSING",
+        ]);
+
+        cmd.assert().failure();
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let cmd = cmd.args([
+            "compare",
+            "--configs",
+            "tests/fixtures/configs",
+            "TEST",
+            "ENVTYPE",
+            "--format",
+            "json",
+        ]);
+
+        let output = cmd.output().unwrap();
+        assert!(output.status.success());
+        let comparison: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(comparison["From"], "TEST");
+        assert_eq!(comparison["To"], "ENVTYPE");
+        assert_eq!(comparison["Changes"].as_array().unwrap().len(), 2);
+    }
+
+    #[cfg(not(all(target_env = "msvc", target_arch = "x86_64")))]
+    #[test]
+    fn test_log() {
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        let repo = init_config_repo(temp_dir.path());
+        let configs_path = temp_dir.path().join("configs");
+
+        update_config(
+            &configs_path,
+            "TEST",
+            r#""Key": "TEST""#,
+            r#""Key": "UPDATED""#,
+        );
+        let update = commit_all(&repo, "Update TEST region");
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let cmd = cmd.args([
+            "log",
+            "--configs",
+            configs_path.to_str().unwrap(),
+            "TEST",
+            "Region.Key",
+            "-n",
+            "1",
+        ]);
+
+        cmd.assert().success();
+        cmd.assert().stdout(
+            predicate::str::starts_with(format!("{} ", &update[..7]))
+                .and(predicate::str::contains(
+                    "Hogan <hogan@example.com>\n    Update TEST region\n    \"TEST\" -> \"UPDATED\"\n",
+                ))
+                .from_utf8(),
+        );
+    }
+
+    #[cfg(not(all(target_env = "msvc", target_arch = "x86_64")))]
+    #[test]
+    fn test_output_pattern() {
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        fs_extra::copy_items(
+            &["tests/fixtures/projects/templates"],
+            temp_dir.path(),
+            &dir::CopyOptions::new(),
+        )
+        .unwrap();
+
+        let templates_path = temp_dir.path().join("templates");
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let cmd = cmd.args([
+            "transform",
+            "--configs",
+            "tests/fixtures/configs",
+            "--templates",
+            templates_path.to_str().unwrap(),
+            "--output-pattern",
+            "out/{env}/{dir}/{stem}.{ext}",
+        ]);
+
+        cmd.assert().success();
+
+        for environment in ["EMPTY", "ENVTYPE", "TEST", "TEST2"].iter() {
+            for (project, file) in [
+                ("project-1", "config"),
+                ("project-1", "yaml"),
+                ("project-2", "config"),
+            ]
+            .iter()
+            {
+                let rendered = std::fs::read_to_string(
+                    templates_path
+                        .join("out")
+                        .join(environment)
+                        .join(project)
+                        .join(format!("Web.{}", file)),
+                )
+                .unwrap();
+                let expected = std::fs::read_to_string(
+                    Path::new("tests/fixtures/projects/rendered")
+                        .join(project)
+                        .join(format!("Web.{}.{}", environment, file)),
+                )
+                .unwrap();
+                assert_eq!(rendered, expected);
+            }
+        }
+
+        assert!(!templates_path.join("project-1/Web.TEST.config").exists());
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let cmd = cmd.args([
+            "transform",
+            "--configs",
+            "tests/fixtures/configs",
+            "--templates",
+            templates_path.to_str().unwrap(),
+            "--output-pattern",
+            "out/{environment}/{stem}.{ext}",
+        ]);
+
+        cmd.assert().failure();
+    }
+
+    #[cfg(not(all(target_env = "msvc", target_arch = "x86_64")))]
+    #[test]
+    fn test_output_dir() {
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        fs_extra::copy_items(
+            &["tests/fixtures/projects/templates"],
+            temp_dir.path(),
+            &dir::CopyOptions::new(),
+        )
+        .unwrap();
+
+        let templates_path = temp_dir.path().join("templates");
+        let output_path = temp_dir.path().join("out");
+
+        let ignore_path = output_path.join("project-1/Web.EMPTY.config");
+        std::fs::create_dir_all(ignore_path.parent().unwrap()).unwrap();
+        std::fs::write(&ignore_path, "Hamburger.").unwrap();
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let cmd = cmd.args([
+            "transform",
+            "--configs",
+            "tests/fixtures/configs",
+            "--templates",
+            templates_path.to_str().unwrap(),
+            "--templates-filter",
+            "^Web\\.template\\.config$",
+            "--output-dir",
+            output_path.to_str().unwrap(),
+            "-i",
+        ]);
+
+        cmd.assert().success();
+
+        assert_eq!(std::fs::read_to_string(&ignore_path).unwrap(), "Hamburger.");
+
+        for (project, environment) in [
+            ("project-1", "TEST"),
+            ("project-1", "TEST2"),
+            ("project-2", "EMPTY"),
+            ("project-2", "ENVTYPE"),
+        ]
+        .iter()
+        {
+            let file_name = format!("Web.{}.config", environment);
+            assert_eq!(
+                std::fs::read_to_string(output_path.join(project).join(&file_name)).unwrap(),
+                std::fs::read_to_string(
+                    Path::new("tests/fixtures/projects/rendered")
+                        .join(project)
+                        .join(&file_name)
+                )
+                .unwrap()
+            );
+        }
+
+        assert!(!output_path.join("project-1/Web.TEST.yaml").exists());
+        assert!(!dir_diff::is_different(
+            templates_path,
+            Path::new("tests/fixtures/projects/templates")
+        )
+        .unwrap());
+    }
+
+    #[cfg(not(all(target_env = "msvc", target_arch = "x86_64")))]
+    #[test]
+    fn test_check() {
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        fs_extra::copy_items(
+            &["tests/fixtures/projects/rendered"],
+            temp_dir.path(),
+            &dir::CopyOptions::new(),
+        )
+        .unwrap();
+
+        let templates_path = temp_dir.path().join("rendered");
+        let check_args = [
+            "transform",
+            "--configs",
+            "tests/fixtures/configs",
+            "--templates",
+            templates_path.to_str().unwrap(),
+            "--check",
+        ];
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let cmd = cmd.args(check_args);
+        cmd.assert().success();
+        cmd.assert().stdout(
+            predicate::str::contains("Checking templates for TEST")
+                .and(predicate::str::contains(
+                    "0 new, 0 changed and 12 unchanged file(s)",
+                ))
+                .from_utf8(),
+        );
+
+        let changed_path = templates_path.join("project-1/Web.TEST.yaml");
+        let removed_path = templates_path.join("project-2/Web.TEST2.config");
+        std::fs::write(&changed_path, "Hamburger.").unwrap();
+        std::fs::remove_file(&removed_path).unwrap();
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let cmd = cmd.args(check_args);
+        cmd.assert().failure();
+        cmd.assert().stdout(
+            predicate::str::contains(format!("  changed   {}", changed_path.display()))
+                .and(predicate::str::contains(format!(
+                    "  new       {}",
+                    removed_path.display()
+                )))
+                .and(predicate::str::contains(
+                    "1 new, 1 changed and 10 unchanged file(s)",
+                ))
+                .from_utf8(),
+        );
+
+        assert!(!removed_path.exists());
+        assert_eq!(
+            std::fs::read_to_string(&changed_path).unwrap(),
+            "Hamburger."
+        );
+    }
+
+    #[cfg(not(all(target_env = "msvc", target_arch = "x86_64")))]
+    #[test]
+    fn test_diff_output() {
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        fs_extra::copy_items(
+            &["tests/fixtures/projects/rendered"],
+            temp_dir.path(),
+            &dir::CopyOptions::new(),
+        )
+        .unwrap();
+
+        let templates_path = temp_dir.path().join("rendered");
+        let changed_path = templates_path.join("project-1/Web.TEST.yaml");
+        let original = std::fs::read_to_string(&changed_path).unwrap();
+        std::fs::write(
+            &changed_path,
+            original.replace("lowercase: uppercase", "lowercase: stale"),
+        )
+        .unwrap();
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let cmd = cmd.args([
+            "transform",
+            "--configs",
+            "tests/fixtures/configs",
+            "--templates",
+            templates_path.to_str().unwrap(),
+            "--environments-filter",
+            "TEST",
+            "--diff",
+            "--check",
+        ]);
+        cmd.assert().failure();
+        cmd.assert().stdout(
+            predicate::str::contains(format!(
+                "Template {}",
+                templates_path.join("project-1/Web.template.yaml").display()
+            ))
+            .and(predicate::str::contains(format!(
+                "--- a/{}",
+                changed_path.display()
+            )))
+            .and(predicate::str::contains(
+                "-lowercase: stale\n+lowercase: uppercase",
+            ))
+            .and(predicate::str::contains("Web.template.config").not())
+            .from_utf8(),
+        );
+        assert_ne!(std::fs::read_to_string(&changed_path).unwrap(), original);
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let cmd = cmd.args([
+            "transform",
+            "--configs",
+            "tests/fixtures/configs",
+            "--templates",
+            templates_path.to_str().unwrap(),
+            "--environments-filter",
+            "TEST",
+            "--diff",
+        ]);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("+lowercase: uppercase").from_utf8());
+        assert_eq!(std::fs::read_to_string(&changed_path).unwrap(), original);
+    }
+
+    #[cfg(not(all(target_env = "msvc", target_arch = "x86_64")))]
+    #[test]
+    fn test_partials() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let templates_path = temp_dir.path().join("templates");
+        let shared_path = temp_dir.path().join("shared");
+        std::fs::create_dir_all(templates_path.join("_partials")).unwrap();
+        std::fs::create_dir_all(shared_path.join("db")).unwrap();
+
+        std::fs::write(
+            templates_path.join("_partials/logging.template.config"),
+            "<logging region=\"{{Region.Key}}\" />",
+        )
+        .unwrap();
+        std::fs::write(shared_path.join("db/endpoint.hbs"), "{{{DB.Endpoint}}}").unwrap();
+        std::fs::write(
+            templates_path.join("Web.template.config"),
+            "{{> logging}}\n<db endpoint=\"{{> db/endpoint}}\" />\n",
+        )
+        .unwrap();
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let cmd = cmd.args([
+            "transform",
+            "--configs",
+            "tests/fixtures/configs",
+            "--templates",
+            templates_path.to_str().unwrap(),
+            "--environments-filter",
+            "TEST",
+            "--partials",
+            shared_path.to_str().unwrap(),
+        ]);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("Loaded 1 template file(s)").from_utf8());
+
+        assert_eq!(
+            std::fs::read_to_string(templates_path.join("Web.TEST.config")).unwrap(),
+            "<logging region=\"TEST\" />\n<db endpoint=\"host-name\\TEST\"\" />\n"
+        );
+        assert!(!templates_path
+            .join("_partials/logging.TEST.config")
+            .exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_front_matter() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let templates_path = temp_dir.path();
+
+        std::fs::write(
+            templates_path.join("Typed.template.config"),
+            "---hogan\nenvironmentTypes: alpha\noutput: out/{env}/{stem}.{ext}\nmode: 0640\n---\n{{Region.Key}}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            templates_path.join("Named.template.config"),
+            "---hogan\nenvironments: TEST2?\n---\n{{Region.Key}}\n",
+        )
+        .unwrap();
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let cmd = cmd.args([
+            "transform",
+            "--configs",
+            "tests/fixtures/configs",
+            "--templates",
+            templates_path.to_str().unwrap(),
+        ]);
+        cmd.assert().success();
+
+        let typed_path = templates_path.join("out/ENVTYPE/Typed.config");
+        assert!(std::fs::read_to_string(&typed_path).is_ok());
+        assert_eq!(
+            std::fs::metadata(&typed_path).unwrap().permissions().mode() & 0o777,
+            0o640
+        );
+        assert!(!templates_path.join("out/TEST").exists());
+        assert!(!templates_path.join("Typed.ENVTYPE.config").exists());
+
+        assert_eq!(
+            std::fs::read_to_string(templates_path.join("Named.TEST.config")).unwrap(),
+            "TEST\n"
+        );
+        assert!(templates_path.join("Named.TEST2.config").exists());
+        assert!(!templates_path.join("Named.ENVTYPE.config").exists());
+        assert!(!templates_path.join("Named.EMPTY.config").exists());
+    }
+
+    #[cfg(not(all(target_env = "msvc", target_arch = "x86_64")))]
+    #[test]
+    fn test_yaml_document_and_invalid_front_matter() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let templates_path = temp_dir.path();
+
+        std::fs::write(
+            templates_path.join("app.template.yaml"),
+            "---\nRegion: {{Region.Key}}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            templates_path.join("bad.template.yaml"),
+            "---hogan\nRegion: {{Region.Key}}\n---\n",
+        )
+        .unwrap();
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args([
+            "transform",
+            "--configs",
+            "tests/fixtures/configs",
+            "--templates",
+            templates_path.to_str().unwrap(),
+            "--environments-filter",
+            "TEST",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "Skipping {}",
+            templates_path.join("bad.template.yaml").display()
+        )))
+        .stderr(predicate::str::contains(
+            "Rendering failed. 1 template(s) have invalid front matter",
+        ));
+
+        // The valid templates are still rendered
+        assert_eq!(
+            std::fs::read_to_string(templates_path.join("app.TEST.yaml")).unwrap(),
+            "---\nRegion: TEST\n"
+        );
+        assert!(!templates_path.join("bad.TEST.yaml").exists());
+
+        // A drift check must not pass while a template can't be loaded
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args([
+            "transform",
+            "--configs",
+            "tests/fixtures/configs",
+            "--templates",
+            templates_path.to_str().unwrap(),
+            "--environments-filter",
+            "TEST",
+            "--check",
+        ])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "0 new, 0 changed and 1 unchanged file(s)",
+        ))
+        .stderr(predicate::str::contains(
+            "1 template(s) have invalid front matter",
+        ));
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args(["validate", "--templates", templates_path.to_str().unwrap()])
+            .assert()
+            .failure()
+            .stdout(predicate::str::contains("Unknown key Region"))
+            .stdout(predicate::str::contains(
+                "Validated 2 template(s), found 1 problem(s)",
+            ));
+    }
+
+    #[cfg(not(all(target_env = "msvc", target_arch = "x86_64")))]
+    #[test]
+    fn test_escape_by_extension() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let templates_path = temp_dir.path();
+        for name in &[
+            "Web.template.config",
+            "App.template.yaml",
+            "App.template.properties",
+        ] {
+            std::fs::write(templates_path.join(name), "{{DB.Endpoint}}").unwrap();
+        }
+
+        let transform = |escape_by_extension: bool| {
+            let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+            cmd.args([
+                "transform",
+                "--configs",
+                "tests/fixtures/configs",
+                "--templates",
+                templates_path.to_str().unwrap(),
+                "--environments-filter",
+                "TEST",
+            ]);
+            if escape_by_extension {
+                cmd.arg("--escape-by-extension");
+            }
+            cmd.assert().success();
+        };
+        let read = |name: &str| std::fs::read_to_string(templates_path.join(name)).unwrap();
+
+        transform(false);
+        assert_eq!(read("App.TEST.yaml"), "host-name\\TEST&quot;");
+
+        transform(true);
+        assert_eq!(read("Web.TEST.config"), "host-name\\TEST&quot;");
+        assert_eq!(read("App.TEST.yaml"), "host-name\\\\TEST\\\"");
+        assert_eq!(read("App.TEST.properties"), "host-name\\\\TEST\"");
+    }
+
+    #[cfg(not(all(target_env = "msvc", target_arch = "x86_64")))]
+    #[test]
+    fn test_bundle() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let bundle_path = temp_dir.path().join("out.zip");
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let cmd = cmd.args([
+            "transform",
+            "--configs",
+            "tests/fixtures/configs",
+            "--templates",
+            "tests/fixtures/projects/templates",
+            "--bundle",
+            bundle_path.to_str().unwrap(),
+        ]);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("Wrote 24 file(s)").from_utf8());
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&bundle_path).unwrap()).unwrap();
+        assert_eq!(archive.len(), 24);
+
+        let mut bundled = String::new();
+        std::io::Read::read_to_string(
+            &mut archive.by_name("project-1/Web.TEST.yaml").unwrap(),
+            &mut bundled,
+        )
+        .unwrap();
+        assert_eq!(
+            bundled,
+            std::fs::read_to_string("tests/fixtures/projects/rendered/project-1/Web.TEST.yaml")
+                .unwrap()
+        );
+        assert!(
+            !std::path::Path::new("tests/fixtures/projects/templates/project-1/Web.TEST.yaml")
+                .exists()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_atomic_writes() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let templates_path = temp_dir.path();
+        let template_path = templates_path.join("run.template.sh");
+        std::fs::write(&template_path, "echo {{Region.Key}}\n").unwrap();
+        std::fs::set_permissions(&template_path, std::fs::Permissions::from_mode(0o750)).unwrap();
+
+        let transform = || {
+            let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+            cmd.args([
+                "transform",
+                "--configs",
+                "tests/fixtures/configs",
+                "--templates",
+                templates_path.to_str().unwrap(),
+                "--templates-filter",
+                "template\\.sh$",
+                "--environments-filter",
+                "TEST",
+            ])
+            .assert()
+            .success();
+        };
+
+        transform();
+        let output_path = templates_path.join("run.TEST.sh");
+        assert_eq!(
+            std::fs::read_to_string(&output_path).unwrap(),
+            "echo TEST\n"
+        );
+        assert_eq!(
+            std::fs::metadata(&output_path)
+                .unwrap()
+                .permissions()
+                .mode()
+                & 0o777,
+            0o750
+        );
+
+        // Unchanged contents are not rewritten, only the permissions are restored
+        let modified = std::fs::metadata(&output_path).unwrap().modified().unwrap();
+        std::fs::set_permissions(&output_path, std::fs::Permissions::from_mode(0o600)).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        transform();
+        let metadata = std::fs::metadata(&output_path).unwrap();
+        assert_eq!(metadata.modified().unwrap(), modified);
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o750);
+
+        let mut files = std::fs::read_dir(templates_path)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        files.sort_unstable();
+        assert_eq!(
+            files,
+            vec![".hogan-manifest.json", "run.TEST.sh", "run.template.sh"]
+        );
+    }
+
+    #[cfg(not(all(target_env = "msvc", target_arch = "x86_64")))]
+    #[test]
+    fn test_manifest_and_prune() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let configs_path = temp_dir.path().join("configs");
+        let templates_path = temp_dir.path().join("templates");
+        fs_extra::copy_items(
+            &[
+                "tests/fixtures/configs",
+                "tests/fixtures/projects/templates",
+            ],
+            temp_dir.path(),
+            &dir::CopyOptions::new(),
+        )
+        .unwrap();
+
+        let transform = |extra_args: &[&str]| {
+            let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+            cmd.args([
+                "transform",
+                "--configs",
+                configs_path.to_str().unwrap(),
+                "--templates",
+                templates_path.to_str().unwrap(),
+            ])
+            .args(extra_args)
+            .assert()
+            .success()
+        };
+
+        transform(&[]);
+        let manifest = Manifest::load(&templates_path.join(MANIFEST_FILE)).unwrap();
+        assert_eq!(manifest.entries.len(), 24);
+        let entry = manifest
+            .entries
+            .iter()
+            .find(|e| e.path == Path::new("project-1/Web.TEST2.yaml"))
+            .unwrap();
+        assert_eq!(entry.template, Path::new("project-1/Web.template.yaml"));
+        assert_eq!(entry.environment, "TEST2");
+        assert_eq!(
+            entry.hash,
+            content_hash(&std::fs::read(templates_path.join("project-1/Web.TEST2.yaml")).unwrap())
+        );
+
+        std::fs::remove_file(configs_path.join("config.TEST2.json")).unwrap();
+
+        // A filtered run leaves the outputs of other environments alone
+        transform(&["--environments-filter", "TEST", "--prune"]);
+        assert!(templates_path.join("project-1/Web.TEST2.yaml").exists());
+
+        transform(&[]).stdout(
+            predicate::str::contains("6 previously generated file(s) are no longer generated")
+                .from_utf8(),
+        );
+        assert!(templates_path.join("project-1/Web.TEST2.yaml").exists());
+
+        transform(&["--prune"]).stdout(predicate::str::contains("Pruned").from_utf8());
+        assert!(!templates_path.join("project-1/Web.TEST2.yaml").exists());
+        assert!(templates_path.join("project-1/Web.TEST.yaml").exists());
+        let manifest = Manifest::load(&templates_path.join(MANIFEST_FILE)).unwrap();
+        assert_eq!(manifest.entries.len(), 18);
+        assert!(manifest.entries.iter().all(|e| e.environment != "TEST2"));
+    }
+
+    #[cfg(not(all(target_env = "msvc", target_arch = "x86_64")))]
+    #[test]
+    fn test_prune_keeps_outputs_of_invalid_templates() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs_extra::copy_items(
+            &["tests/fixtures/projects/templates"],
+            temp_dir.path(),
+            &dir::CopyOptions::new(),
+        )
+        .unwrap();
+        let templates_path = temp_dir.path().join("templates");
+
+        let transform = |extra_args: &[&str]| {
+            let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+            cmd.args([
+                "transform",
+                "--configs",
+                "tests/fixtures/configs",
+                "--templates",
+                templates_path.to_str().unwrap(),
+            ])
+            .args(extra_args)
+            .assert()
+        };
+
+        transform(&[]).success();
+
+        let template_path = templates_path.join("project-2/Web.template.config");
+        let template = std::fs::read_to_string(&template_path).unwrap();
+        std::fs::write(
+            &template_path,
+            format!("---hogan\nbogus: 1\n---\n{}", template),
+        )
+        .unwrap();
+
+        transform(&["--prune"])
+            .failure()
+            .stdout(predicate::str::contains("Pruned").not().from_utf8());
+        for environment in ["EMPTY", "ENVTYPE", "TEST", "TEST2"].iter() {
+            assert!(templates_path
+                .join(format!("project-2/Web.{}.config", environment))
+                .exists());
+        }
+        let manifest = Manifest::load(&templates_path.join(MANIFEST_FILE)).unwrap();
+        assert_eq!(manifest.entries.len(), 24);
+    }
+
+    #[cfg(not(all(target_env = "msvc", target_arch = "x86_64")))]
+    #[test]
+    fn test_parallel_rendering() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs_extra::copy_items(
+            &["tests/fixtures/projects/templates"],
+            temp_dir.path(),
+            &dir::CopyOptions::new(),
+        )
+        .unwrap();
+        let templates_path = temp_dir.path().join("templates");
+
+        let transform = |jobs: &str| {
+            let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+            let output = cmd
+                .args([
+                    "transform",
+                    "--configs",
+                    "tests/fixtures/configs",
+                    "--templates",
+                    templates_path.to_str().unwrap(),
+                    "--jobs",
+                    jobs,
+                ])
+                .output()
+                .unwrap();
+            (
+                output.status.success(),
+                String::from_utf8(output.stdout).unwrap(),
+                String::from_utf8(output.stderr).unwrap(),
+            )
+        };
+
+        let (success, sequential, _) = transform("1");
+        assert!(success);
+        let (success, parallel, _) = transform("4");
+        assert!(success);
+        assert_eq!(sequential, parallel);
+
+        std::fs::write(
+            templates_path.join("project-2/Web.template.config"),
+            "{{#each}}",
+        )
+        .unwrap();
+        std::fs::write(
+            templates_path.join("project-1/Web.template.yaml"),
+            "changed: {{Region.Key}}",
+        )
+        .unwrap();
+
+        let (success, _, errors) = transform("4");
+        assert!(!success);
+        assert!(errors.contains("Failed to render 4 file(s):"));
+        for env in &["TEST", "TEST2", "ENVTYPE", "EMPTY"] {
+            assert!(errors.contains(&format!(
+                "{} for {}: ",
+                templates_path
+                    .join("project-2/Web.template.config")
+                    .display(),
+                env
+            )));
+        }
+        assert!(errors.contains("Rendering failed. 4 file(s) could not be rendered"));
+        assert_eq!(
+            std::fs::read_to_string(templates_path.join("project-1/Web.TEST.yaml")).unwrap(),
+            "changed: TEST"
+        );
+    }
+
+    #[cfg(not(all(target_env = "msvc", target_arch = "x86_64")))]
+    #[test]
+    fn test_report_missing() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs_extra::copy_items(
+            &["tests/fixtures/projects/templates"],
+            temp_dir.path(),
+            &dir::CopyOptions::new(),
+        )
+        .unwrap();
+        let templates_path = temp_dir.path().join("templates");
+        std::fs::write(
+            templates_path.join("project-1/Extra.template.yaml"),
+            "---hogan\nenvironments: TEST.*\n---\na: {{Nope.Key}}\nb: {{Region.Key}} {{Missing}}\n",
+        )
+        .unwrap();
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args([
+            "transform",
+            "--configs",
+            "tests/fixtures/configs",
+            "--templates",
+            templates_path.to_str().unwrap(),
+            "--report-missing",
+        ])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "project-1/Extra.template.yaml     4       4  Nope.Key         TEST, TEST2",
+        ))
+        .stdout(predicate::str::contains(
+            "project-1/Extra.template.yaml     5      19  Missing          TEST, TEST2",
+        ))
+        .stdout(predicate::str::contains(
+            "project-2/Web.template.config     4      39  EnvironmentType  TEST, TEST2",
+        ))
+        .stderr(predicate::str::contains(
+            "Rendering failed. 8 missing value(s) in 6 file(s)",
+        ));
+
+        assert!(!templates_path.join("project-1/Extra.TEST.yaml").exists());
+        assert!(!templates_path.join("project-1/Web.TEST.config").exists());
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let templates_path = temp_dir.path();
+        std::fs::create_dir_all(templates_path.join("_partials")).unwrap();
+        std::fs::write(
+            templates_path.join("_partials/db.hbs"),
+            "{{Nope.Host}} {{Nope.Port}}",
+        )
+        .unwrap();
+        std::fs::write(
+            templates_path.join("Web.template.config"),
+            "{{Missing}} {{> db}}",
+        )
+        .unwrap();
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args([
+            "transform",
+            "--configs",
+            "tests/fixtures/configs",
+            "--templates",
+            templates_path.to_str().unwrap(),
+            "--environments-filter",
+            "TEST",
+            "--report-missing",
+        ])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "Web.template.config (partial db)     1       1  Nope.Host  TEST",
+        ))
+        .stdout(predicate::str::contains("Nope.Port").not())
+        .stderr(predicate::str::contains(
+            "2 missing value(s) in 1 file(s), results for 1 file(s) are incomplete as only the first missing value in a partial is found",
+        ));
+    }
+
+    #[cfg(not(all(target_env = "msvc", target_arch = "x86_64")))]
+    #[test]
+    fn test_vars() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args([
+            "vars",
+            "tests/fixtures/projects/templates/project-1/Web.template.config",
+            "--configs",
+            "tests/fixtures/configs",
+            "--templates",
+            "tests/fixtures/projects/templates",
+        ])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "EnvironmentType                 4      39           TEST, TEST2, EMPTY",
+        ))
+        .stdout(predicate::str::contains(
+            "Memcache.Servers.*.Endpoint     6      71",
+        ))
+        .stdout(predicate::str::contains(
+            "6 path(s) referenced, checked against 4 environment(s)",
+        ))
+        .stderr(predicate::str::contains(
+            "1 referenced path(s) are missing from at least one environment",
+        ));
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args([
+            "vars",
+            "tests/fixtures/projects/templates/project-1/Web.template.config",
+            "--configs",
+            "tests/fixtures/configs",
+            "--environments-filter",
+            "ENVTYPE",
+            "--format",
+            "json",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            r#""Path": "Memcache.Servers.*.Port""#,
+        ))
+        .stdout(predicate::str::contains(r#""MissingFrom": []"#));
+
+        // Loading partials must not add anything to the JSON on stdout
+        let temp_dir = tempfile::tempdir().unwrap();
+        let templates_path = temp_dir.path();
+        std::fs::create_dir_all(templates_path.join("_partials")).unwrap();
+        std::fs::write(
+            templates_path.join("_partials/region.hbs"),
+            "{{Region.Key}}",
+        )
+        .unwrap();
+        let template_path = templates_path.join("Web.template.config");
+        std::fs::write(&template_path, "{{> region}} {{Nope}}").unwrap();
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let output = cmd
+            .args([
+                "vars",
+                template_path.to_str().unwrap(),
+                "--configs",
+                "tests/fixtures/configs",
+                "--templates",
+                templates_path.to_str().unwrap(),
+                "--environments-filter",
+                "TEST",
+                "--format",
+                "json",
+            ])
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        let checks: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(
+            checks,
+            serde_json::json!([
+                {
+                    "Path": "Region.Key",
+                    "Partial": "region",
+                    "Line": 1,
+                    "Column": 1,
+                    "Optional": false,
+                    "ValueUsed": true,
+                    "MissingFrom": []
+                },
+                {
+                    "Path": "Nope",
+                    "Partial": null,
+                    "Line": 1,
+                    "Column": 14,
+                    "Optional": false,
+                    "ValueUsed": true,
+                    "MissingFrom": ["TEST"]
+                }
+            ])
+        );
+    }
+
+    #[cfg(not(all(target_env = "msvc", target_arch = "x86_64")))]
+    #[test]
+    fn test_coverage() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs_extra::copy_items(
+            &["tests/fixtures/configs"],
+            temp_dir.path(),
+            &dir::CopyOptions::new(),
+        )
+        .unwrap();
+        let configs_path = temp_dir.path().join("configs");
+        std::fs::write(
+            configs_path.join("envTypes/global.json"),
+            r#"{"EnvironmentType": "global", "ConfigData": {"Legacy": {"Timeout": 30}}}"#,
+        )
+        .unwrap();
+        let mut test: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(configs_path.join("config.TEST.json")).unwrap())
+                .unwrap();
+        test["ConfigData"]["Legacy"] = serde_json::json!({"Timeout": 10, "Retries": 3});
+        std::fs::write(
+            configs_path.join("config.TEST.json"),
+            serde_json::to_vec(&test).unwrap(),
+        )
+        .unwrap();
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args([
+            "coverage",
+            "--configs",
+            configs_path.to_str().unwrap(),
+            "--templates",
+            "tests/fixtures/projects/templates",
+            "--unused",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Key             Used  Global  Types  Environments\n\
+             Legacy.Retries  no                   TEST\n\
+             Legacy.Timeout  no    yes            TEST\n",
+        ))
+        .stdout(predicate::str::contains(
+            "2 of 14 key(s) are not used by any of 6 template(s) across 4 environment(s)",
+        ));
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args([
+            "coverage",
+            "--configs",
+            configs_path.to_str().unwrap(),
+            "--templates",
+            "tests/fixtures/projects/templates",
+            "--unused",
+            "--format",
+            "json",
+        ]);
+
+        let output = cmd.output().unwrap();
+        assert!(output.status.success());
+        let keys: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(
+            keys,
+            serde_json::json!([
+                {
+                    "Path": "Legacy.Retries",
+                    "Global": false,
+                    "EnvironmentTypes": [],
+                    "Environments": ["TEST"],
+                    "Used": false
+                },
+                {
+                    "Path": "Legacy.Timeout",
+                    "Global": true,
+                    "EnvironmentTypes": [],
+                    "Environments": ["TEST"],
+                    "Used": false
+                }
+            ])
+        );
+    }
+
+    #[cfg(not(all(target_env = "msvc", target_arch = "x86_64")))]
+    #[test]
+    fn test_validate() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args([
+            "validate",
+            "--templates",
+            "tests/fixtures/projects/templates",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Validated 6 template(s), found 0 problem(s)",
+        ));
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs_extra::copy_items(
+            &["tests/fixtures/projects/templates"],
+            temp_dir.path(),
+            &dir::CopyOptions::new(),
+        )
+        .unwrap();
+        let templates_path = temp_dir.path().join("templates");
+        std::fs::write(
+            templates_path.join("project-1/Bad.template.yaml"),
+            "---hogan\nmode: 0600\n---\n{{#if A}}\n{{or B}} {{upper C}} {{> missing}}\n{{/if}}",
+        )
+        .unwrap();
+        std::fs::write(
+            templates_path.join("project-2/Broken.template.yaml"),
+            "{{#each A}}\n{{/with}}",
+        )
+        .unwrap();
+
+        let bad = templates_path.join("project-1/Bad.template.yaml");
+        let broken = templates_path.join("project-2/Broken.template.yaml");
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args(["validate", "--templates", templates_path.to_str().unwrap()])
+            .assert()
+            .failure()
+            .stdout(predicate::str::contains(format!(
+                "{}:5:1: Helper or requires at least 2 parameter(s), found 1",
+                bad.display()
+            )))
+            .stdout(predicate::str::contains(format!(
+                "{}:5:10: Unknown helper upper",
+                bad.display()
+            )))
+            .stdout(predicate::str::contains(format!(
+                "{}:5:22: Unknown partial missing",
+                bad.display()
+            )))
+            .stdout(predicate::str::contains(format!(
+                "{}:2:1: helper \"each\" was opened, but \"with\" is closing",
+                broken.display()
+            )))
+            .stderr(predicate::str::contains(
+                "4 problem(s) found in 2 template(s)",
+            ));
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let output = cmd
+            .args([
+                "validate",
+                "--templates",
+                templates_path.to_str().unwrap(),
+                "--templates-filter",
+                "^Broken",
+                "--format",
+                "json",
+            ])
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        let results: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(
+            results,
+            serde_json::json!([{
+                "Template": broken.to_str().unwrap(),
+                "Issues": [{
+                    "Line": 2,
+                    "Column": 1,
+                    "Message": "helper \"each\" was opened, but \"with\" is closing"
+                }]
+            }])
+        );
+    }
 }
@@ -1,7 +1,11 @@
 use anyhow::Result;
 use hogan::config::ConfigUrl;
+use hogan::error::HoganError;
+use hogan::template::OutputPattern;
 use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
 use std::path::PathBuf;
+use std::str::FromStr;
 use structopt::clap::AppSettings;
 use structopt::StructOpt;
 
@@ -55,9 +59,207 @@ pub enum AppCommand {
         )]
         templates_regex: Regex,
 
-        /// Ignore existing config files intead of overwriting
-        #[structopt(short = "i", long = "ignore-existing")]
-        ignore_existing: bool,
+        #[structopt(flatten)]
+        options: TransformOptions,
+    },
+    /// Show how the merged configs of environments changed between two revisions
+    #[structopt(name = "diff")]
+    Diff {
+        #[structopt(flatten)]
+        common: AppCommon,
+
+        /// Revision to compare from
+        #[structopt(long = "from", value_name = "SHA")]
+        from: String,
+
+        /// Revision to compare to
+        #[structopt(long = "to", value_name = "SHA")]
+        to: String,
+
+        /// Filter environments to compare
+        #[structopt(
+            short = "e",
+            long = "environments-filter",
+            parse(try_from_str = App::parse_regex),
+            default_value = ".+",
+            value_name = "REGEX"
+        )]
+        environments_regex: Regex,
+
+        /// Output format. Either text or json
+        #[structopt(long = "format", default_value = "text", value_name = "FORMAT")]
+        format: OutputFormat,
+    },
+    /// Show the differences between the merged configs of two environments
+    #[structopt(name = "compare")]
+    Compare {
+        #[structopt(flatten)]
+        common: AppCommon,
+
+        /// Environment to compare from
+        #[structopt(value_name = "FROM_ENV")]
+        from: String,
+
+        /// Environment to compare to
+        #[structopt(value_name = "TO_ENV")]
+        to: String,
+
+        /// Revision to compare the environments at. Defaults to the current configs
+        #[structopt(long = "sha", value_name = "SHA")]
+        sha: Option<String>,
+
+        /// Hide differences that only exist because of the environment name
+        #[structopt(long = "collapse-env-names")]
+        collapse_env_names: bool,
+
+        /// Output format. Either text or json
+        #[structopt(long = "format", default_value = "text", value_name = "FORMAT")]
+        format: OutputFormat,
+    },
+    /// List the commits which changed a merged config value for an environment
+    #[structopt(name = "log")]
+    Log {
+        #[structopt(flatten)]
+        common: AppCommon,
+
+        /// Environment to show the history for
+        #[structopt(value_name = "ENV")]
+        environment: String,
+
+        /// Dot separated path of the config value, e.g. DB.Endpoint
+        #[structopt(value_name = "PATH")]
+        path: String,
+
+        /// Revision to start walking history from
+        #[structopt(long = "from", default_value = "HEAD", value_name = "SHA")]
+        from: String,
+
+        /// Maximum number of changes to list
+        #[structopt(short = "n", long = "limit", value_name = "COUNT")]
+        limit: Option<usize>,
+
+        /// Output format. Either text or json
+        #[structopt(long = "format", default_value = "text", value_name = "FORMAT")]
+        format: OutputFormat,
+    },
+    /// List the config paths a template references and the environments missing each of them
+    #[structopt(name = "vars")]
+    Vars {
+        #[structopt(flatten)]
+        common: AppCommon,
+
+        /// Template to list the referenced paths of
+        #[structopt(parse(from_os_str), value_name = "TEMPLATE")]
+        template_path: PathBuf,
+
+        /// Templates directory the template is in. Partials in its _partials directory are
+        /// followed
+        #[structopt(
+            short = "t",
+            long = "templates",
+            parse(from_os_str),
+            default_value = ".",
+            value_name = "DIR"
+        )]
+        templates_path: PathBuf,
+
+        /// Additional directory of partials to follow
+        #[structopt(long = "partials", parse(from_os_str), value_name = "DIR")]
+        partials_path: Option<PathBuf>,
+
+        /// Filter environments to check
+        #[structopt(
+            short = "e",
+            long = "environments-filter",
+            parse(try_from_str = App::parse_regex),
+            default_value = ".+",
+            value_name = "REGEX"
+        )]
+        environments_regex: Regex,
+
+        /// Output format. Either text or json
+        #[structopt(long = "format", default_value = "text", value_name = "FORMAT")]
+        format: OutputFormat,
+    },
+    /// Report which config keys are used by the templates, and where each key is defined
+    #[structopt(name = "coverage")]
+    Coverage {
+        #[structopt(flatten)]
+        common: AppCommon,
+
+        /// Template source (recursive)
+        #[structopt(
+            short = "t",
+            long = "templates",
+            parse(from_os_str),
+            default_value = ".",
+            value_name = "DIR"
+        )]
+        templates_path: PathBuf,
+
+        /// Filter templates to check the config keys against
+        #[structopt(
+            short = "f",
+            long = "templates-filter",
+            parse(try_from_str = App::parse_regex),
+            default_value = "^[^.]*(\\w+\\.)*template([-.].+)?\\.(config|ya?ml|properties)",
+            value_name = "REGEX"
+        )]
+        templates_regex: Regex,
+
+        /// Additional directory of partials to follow
+        #[structopt(long = "partials", parse(from_os_str), value_name = "DIR")]
+        partials_path: Option<PathBuf>,
+
+        /// Filter environments to report the config keys of
+        #[structopt(
+            short = "e",
+            long = "environments-filter",
+            parse(try_from_str = App::parse_regex),
+            default_value = ".+",
+            value_name = "REGEX"
+        )]
+        environments_regex: Regex,
+
+        /// Only list the keys which no template uses
+        #[structopt(long = "unused")]
+        unused: bool,
+
+        /// Output format. Either text or json
+        #[structopt(long = "format", default_value = "text", value_name = "FORMAT")]
+        format: OutputFormat,
+    },
+    /// Check templates for syntax errors, unknown helpers or partials and helpers given the wrong
+    /// number of parameters, without rendering them
+    #[structopt(name = "validate")]
+    Validate {
+        /// Template source (recursive)
+        #[structopt(
+            short = "t",
+            long = "templates",
+            parse(from_os_str),
+            default_value = ".",
+            value_name = "DIR"
+        )]
+        templates_path: PathBuf,
+
+        /// Filter templates to validate
+        #[structopt(
+            short = "f",
+            long = "templates-filter",
+            parse(try_from_str = App::parse_regex),
+            default_value = "^[^.]*(\\w+\\.)*template([-.].+)?\\.(config|ya?ml|properties)",
+            value_name = "REGEX"
+        )]
+        templates_regex: Regex,
+
+        /// Additional directory of partials templates may use
+        #[structopt(long = "partials", parse(from_os_str), value_name = "DIR")]
+        partials_path: Option<PathBuf>,
+
+        /// Output format. Either text or json
+        #[structopt(long = "format", default_value = "text", value_name = "FORMAT")]
+        format: OutputFormat,
     },
     /// Respond to HTTP requests to transform a template
     #[structopt(name = "server")]
@@ -124,6 +326,86 @@ pub enum AppCommand {
     },
 }
 
+/// Options controlling how templates are rendered and where they are written
+#[derive(StructOpt, Debug, Clone)]
+pub struct TransformOptions {
+    /// Ignore existing config files intead of overwriting
+    #[structopt(short = "i", long = "ignore-existing")]
+    pub ignore_existing: bool,
+
+    /// Where to write rendered files, relative to the templates directory. Supports the
+    /// placeholders {env}, {envType}, {stem}, {ext} and {dir} e.g. out/{env}/{dir}/{stem}.{ext}.
+    /// Defaults to replacing "template" in the file name with the environment name
+    #[structopt(long = "output-pattern", value_name = "PATTERN")]
+    pub output_pattern: Option<OutputPattern>,
+
+    /// Write rendered files to this directory, mirroring the structure of the templates
+    /// directory, instead of next to their templates
+    #[structopt(
+        short = "o",
+        long = "output-dir",
+        parse(from_os_str),
+        value_name = "DIR"
+    )]
+    pub output_dir: Option<PathBuf>,
+
+    /// Render every template in memory and compare it with the file on disk without writing
+    /// anything. Exits with an error when any rendered file would be created or changed
+    #[structopt(long = "check")]
+    pub check: bool,
+
+    /// Print a unified diff between each existing output file and its newly rendered contents
+    /// before writing. Combine with --check to only print the diff
+    #[structopt(long = "diff")]
+    pub diff: bool,
+
+    /// Register each file in this directory as a handlebars partial named after its path without
+    /// extensions. A _partials directory in the templates directory is always registered
+    #[structopt(long = "partials", parse(from_os_str), value_name = "DIR")]
+    pub partials_path: Option<PathBuf>,
+
+    /// Write every rendered template into this zip archive instead of to disk, at its output
+    /// path relative to the templates directory
+    #[structopt(
+        long = "bundle",
+        parse(from_os_str),
+        value_name = "FILE",
+        conflicts_with_all = &["check", "diff", "output-dir", "ignore-existing"]
+    )]
+    pub bundle_path: Option<PathBuf>,
+
+    /// Delete previously generated files, recorded in the manifest, which were not generated by
+    /// this run e.g. the outputs of removed environments or templates
+    #[structopt(long = "prune", conflicts_with_all = &["check", "bundle-path"])]
+    pub prune: bool,
+
+    /// Escape {{value}} expressions for the format of each template's output, picked by its file
+    /// extension: .yaml, .yml and .json values are escaped for a double quoted string and
+    /// .properties values as java properties. Other templates keep the HTML escaping
+    #[structopt(long = "escape-by-extension")]
+    pub escape_by_extension: bool,
+
+    /// Number of templates to render concurrently. Defaults to the number of CPUs
+    #[structopt(short = "j", long = "jobs", value_name = "N")]
+    pub jobs: Option<usize>,
+
+    /// Keep running, re-rendering the templates and environments affected whenever a template,
+    /// partial or config file changes. Requires a local configs directory
+    #[structopt(
+        long = "watch",
+        conflicts_with_all = &["check", "bundle-path", "prune"]
+    )]
+    pub watch: bool,
+
+    /// Render every template and environment in strict mode without writing anything, and print
+    /// a table of every value missing from the configs. Exits with an error when any are missing
+    #[structopt(
+        long = "report-missing",
+        conflicts_with_all = &["check", "diff", "bundle-path", "prune", "watch"]
+    )]
+    pub report_missing: bool,
+}
+
 #[derive(StructOpt, Debug, Clone)]
 pub struct AppCommon {
     /// Config source. Accepts file and git URLs. Paths within a git repository may be appended
@@ -160,6 +442,29 @@ pub struct AppCommon {
     pub native_clone: bool,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(HoganError::InvalidConfiguration {
+                param: "format".to_string(),
+                msg: format!("Unknown output format {}", s),
+            }
+            .into()),
+        }
+    }
+}
+
 impl App {
     pub fn config_regex(environment: &Regex) -> Result<Regex> {
         App::parse_regex(&format!("config\\.{}\\.json$", environment))
@@ -176,3 +481,37 @@ impl App {
         PathBuf::from(shellexpand::tilde(src).into_owned())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_transform_options(args: &[&str]) -> structopt::clap::Result<TransformOptions> {
+        TransformOptions::from_iter_safe(Some("transform").iter().chain(args))
+    }
+
+    #[test]
+    fn test_prune_conflicts() {
+        assert!(parse_transform_options(&["--prune"]).unwrap().prune);
+        assert!(parse_transform_options(&["--prune", "--check"]).is_err());
+        assert!(parse_transform_options(&["--prune", "--bundle", "out.zip"]).is_err());
+    }
+
+    #[test]
+    fn test_watch_conflicts() {
+        assert!(parse_transform_options(&["--watch"]).unwrap().watch);
+        assert!(parse_transform_options(&["--watch", "--prune"]).is_err());
+        assert!(parse_transform_options(&["--watch", "--bundle", "out.zip"]).is_err());
+    }
+
+    #[test]
+    fn test_report_missing_conflicts() {
+        assert!(
+            parse_transform_options(&["--report-missing"])
+                .unwrap()
+                .report_missing
+        );
+        assert!(parse_transform_options(&["--report-missing", "--check"]).is_err());
+        assert!(parse_transform_options(&["--report-missing", "--bundle", "out.zip"]).is_err());
+    }
+}
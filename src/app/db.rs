@@ -34,7 +34,7 @@ pub fn read_sql_env(db_path: &str, env: &str, sha: &str) -> Result<Option<Enviro
     let mut query = conn.prepare("SELECT data FROM hogan WHERE key = ? LIMIT 1")?;
     let key = gen_env_key(sha, env);
     let data: Option<rusqlite::Result<Vec<u8>>> =
-        query.query_map(params![key], |row| Ok(row.get(0)?))?.next();
+        query.query_map(params![key], |row| row.get(0))?.next();
     if let Some(data) = data {
         let decompressed_data = data?
             .iter()
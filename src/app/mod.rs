@@ -5,3 +5,4 @@ mod db;
 mod fetch_actor;
 mod head_actor;
 pub mod server;
+mod watch;
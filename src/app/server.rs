@@ -1,4 +1,5 @@
 use crate::app::config::AppCommon;
+use crate::app::config::OutputFormat;
 use crate::app::datadogstatsd::{CustomMetrics, DdMetrics};
 use crate::app::db;
 use crate::app::fetch_actor;
@@ -8,7 +9,10 @@ use actix_web::middleware::Logger;
 use actix_web::{get, middleware, post, web, HttpResponse, HttpServer};
 use anyhow::{Context, Result};
 use hogan::config::ConfigDir;
+use hogan::diff::{self, EnvironmentDiff};
 use hogan::error::HoganError;
+use hogan::history;
+use hogan::template::{FrontMatter, RenderMetadata, Template};
 use lru_time_cache::LruCache;
 use parking_lot::Mutex;
 use regex::Regex;
@@ -16,16 +20,20 @@ use riker::actors::ActorSystem;
 use serde::Deserialize;
 use serde::Serialize;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::SystemTime;
 use tokio::task;
 
 type EnvCache = Mutex<LruCache<String, Arc<hogan::config::Environment>>>;
 type EnvListingCache = Mutex<LruCache<String, Arc<Vec<EnvDescription>>>>;
+type PartialFiles = Vec<(PathBuf, Vec<u8>)>;
+type PartialsCache = Mutex<LruCache<String, Arc<PartialFiles>>>;
 
 struct ServerState {
     environments: EnvCache,
     environment_listings: EnvListingCache,
+    partials: PartialsCache,
     config_dir: Arc<hogan::config::ConfigDir>,
     write_lock: Mutex<usize>,
     environments_regex: Regex,
@@ -70,6 +78,12 @@ fn create_error_response(e: anyhow::Error) -> HttpResponse {
             body.insert("environment", &env);
             HttpResponse::BadRequest().json(body)
         }
+        HoganError::InvalidConfiguration { param, msg } => {
+            let mut body = response_map();
+            body.insert("parameter", &param);
+            body.insert("message", &msg);
+            HttpResponse::BadRequest().json(body)
+        }
         HoganError::UnknownEnvironment { sha, env } => {
             let mut body = response_map();
             body.insert("sha", &sha);
@@ -88,6 +102,7 @@ fn create_error_response(e: anyhow::Error) -> HttpResponse {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn start_up_server(
     common: AppCommon,
     port: u16,
@@ -128,6 +143,10 @@ pub fn start_up_server(
         LruCache::<String, Arc<Vec<EnvDescription>>>::with_capacity(cache_size),
     );
 
+    let partials = Mutex::new(LruCache::<String, Arc<PartialFiles>>::with_capacity(
+        cache_size,
+    ));
+
     let write_lock = Mutex::new(0);
 
     info!("Starting server on {}:{}", address, port);
@@ -135,6 +154,7 @@ pub fn start_up_server(
     let state = ServerState {
         environments,
         environment_listings,
+        partials,
         config_dir,
         write_lock,
         environments_regex,
@@ -169,7 +189,7 @@ impl From<&hogan::config::Environment> for EnvDescription {
 }
 
 fn contextualize_path(path: &str) -> &str {
-    path.split('/').nth(1).unwrap_or_else(|| &"route")
+    path.split('/').nth(1).unwrap_or("route")
 }
 
 #[actix_web::main]
@@ -222,10 +242,14 @@ async fn start_server(address: String, port: u16, state: ServerState) -> Result<
             .app_data(server_state.clone())
             .service(transform_route_sha_env)
             .service(transform_branch_head)
+            .service(transform_bundle)
             .service(get_envs)
             .service(get_config_by_env)
             .service(get_config_by_env_branch)
             .service(get_branch_sha)
+            .service(get_config_diff)
+            .service(get_env_comparison)
+            .service(get_value_history)
             .route("/ok", web::to(|| HttpResponse::Ok().finish()))
     })
     .bind(binding)?
@@ -286,17 +310,128 @@ fn transform_from_sha(
     env_name: &str,
     state: &ServerState,
 ) -> Result<String> {
+    let full_sha = sha;
     let sha = format_sha(sha);
 
-    let env = get_env(&state, None, sha, env_name)?;
+    let env = get_env(state, None, sha, env_name)?;
+
+    let (front_matter, template) =
+        FrontMatter::parse(&data).map_err(|e| HoganError::InvalidTemplate {
+            msg: format!("{:#}", e),
+            env: env_name.to_string(),
+        })?;
+    if front_matter.is_some_and(|f| !f.applies_to(&env)) {
+        return Err(HoganError::InvalidTemplate {
+            msg: "The template's front matter does not apply to this environment".to_string(),
+            env: env_name.to_string(),
+        }
+        .into());
+    }
+
+    let mut handlebars = hogan::transform::handlebars(state.strict);
+    hogan::transform::register_partial_files(&mut handlebars, &get_partials(state, sha)?)?;
+    let data = RenderMetadata::new(&env, Some(full_sha), None).render_data(
+        &handlebars,
+        template,
+        &env.config_data,
+    );
+    handlebars.render_template(template, &data).map_err(|e| {
+        HoganError::InvalidTemplate {
+            msg: format!("Template Error {:?}", e),
+            env: env_name.to_string(),
+        }
+        .into()
+    })
+}
+
+#[derive(Deserialize)]
+struct BundleParams {
+    sha: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct BundleQuery {
+    environments: Option<String>,
+}
+
+#[post("bundle/{sha}/{name}")]
+async fn transform_bundle(
+    data: String,
+    params: web::Path<BundleParams>,
+    query: web::Query<BundleQuery>,
+    state: web::Data<ServerState>,
+) -> HttpResponse {
+    let environments = query
+        .environments
+        .clone()
+        .unwrap_or_else(|| ".+".to_string());
+    let result = match task::spawn_blocking(move || {
+        let sha = if !HEX_REGEX.is_match(&params.sha) {
+            find_branch_head(&params.sha, &state)?
+        } else {
+            params.sha.to_owned()
+        };
+
+        bundle_from_sha(data, &sha, &params.name, &environments, &state)
+    })
+    .await
+    {
+        Ok(r) => r,
+        Err(e) => {
+            warn!("Error joining when bundling a template {:?}", e);
+            Err(e.into())
+        }
+    };
+
+    match result {
+        Ok(bundle) => HttpResponse::Ok()
+            .content_type("application/zip")
+            .body(bundle),
+        Err(e) => create_error_response(e),
+    }
+}
 
-    let handlebars = hogan::transform::handlebars(state.strict);
-    handlebars
-        .render_template(&data, &env.config_data)
+/// Renders the template for every environment whose whole name matches the regex into a zip,
+/// with entries named like a template file of the given name would be on the command line
+fn bundle_from_sha(
+    data: String,
+    sha: &str,
+    name: &str,
+    environments_regex: &str,
+    state: &ServerState,
+) -> Result<Vec<u8>> {
+    let full_sha = sha;
+    let sha = format_sha(sha);
+    let filter = hogan::config::build_regex(&format!("^(?:{})$", environments_regex))?;
+    let template = Template::new(Path::new(""), PathBuf::from(name), &data).map_err(|e| {
+        HoganError::InvalidTemplate {
+            msg: format!("{:#}", e),
+            env: environments_regex.to_string(),
+        }
+    })?;
+
+    let environments = get_env_listing(state, None, sha)?
+        .iter()
+        .filter(|e| filter.is_match(&e.name))
+        .map(|e| get_env(state, None, sha, &e.name).map(|env| (*env).clone()))
+        .collect::<Result<Vec<hogan::config::Environment>>>()?;
+    if environments.is_empty() {
+        return Err(HoganError::UnknownEnvironment {
+            sha: sha.to_owned(),
+            env: environments_regex.to_owned(),
+        }
+        .into());
+    }
+
+    let mut handlebars = hogan::transform::handlebars(state.strict);
+    hogan::transform::register_partial_files(&mut handlebars, &get_partials(state, sha)?)?;
+    template
+        .render_to_zip(&handlebars, &environments, Some(full_sha))
         .map_err(|e| {
             HoganError::InvalidTemplate {
                 msg: format!("Template Error {:?}", e),
-                env: env_name.to_string(),
+                env: environments_regex.to_string(),
             }
             .into()
         })
@@ -389,6 +524,160 @@ async fn get_config_by_env_branch(
     }
 }
 
+#[derive(Deserialize)]
+struct ConfigDiffParams {
+    from: String,
+    to: String,
+    env: String,
+}
+
+#[derive(Deserialize)]
+struct FormatQuery {
+    format: Option<OutputFormat>,
+}
+
+#[get("diff/{from}/{to}/{env}")]
+async fn get_config_diff(
+    params: web::Path<ConfigDiffParams>,
+    query: web::Query<FormatQuery>,
+    state: web::Data<ServerState>,
+) -> HttpResponse {
+    let result = match task::spawn_blocking(move || {
+        let from = get_env(&state, None, format_sha(&params.from), &params.env)?;
+        let to = get_env(&state, None, format_sha(&params.to), &params.env)?;
+
+        Ok(EnvironmentDiff {
+            environment: params.env.to_owned(),
+            changes: diff::diff(&from.config_data, &to.config_data),
+        })
+    })
+    .await
+    {
+        Ok(r) => r,
+        Err(e) => {
+            warn!("Error joining when diffing configs {:?}", e);
+            Err(e.into())
+        }
+    };
+
+    match result {
+        Ok(diff) => format_response(&diff, query.format.unwrap_or(OutputFormat::Json)),
+        Err(e) => create_error_response(e),
+    }
+}
+
+#[derive(Deserialize)]
+struct EnvComparisonParams {
+    sha: String,
+    from: String,
+    to: String,
+}
+
+#[derive(Deserialize)]
+struct EnvComparisonQuery {
+    format: Option<OutputFormat>,
+    collapse: Option<bool>,
+}
+
+#[get("compare/{sha}/{from}/{to}")]
+async fn get_env_comparison(
+    params: web::Path<EnvComparisonParams>,
+    query: web::Query<EnvComparisonQuery>,
+    state: web::Data<ServerState>,
+) -> HttpResponse {
+    let collapse = query.collapse.unwrap_or(false);
+    let result = match task::spawn_blocking(move || {
+        let sha = format_sha(&params.sha);
+        let from = get_env(&state, None, sha, &params.from)?;
+        let to = get_env(&state, None, sha, &params.to)?;
+
+        Ok(diff::compare_environments(&from, &to, collapse))
+    })
+    .await
+    {
+        Ok(r) => r,
+        Err(e) => {
+            warn!("Error joining when comparing environments {:?}", e);
+            Err(e.into())
+        }
+    };
+
+    match result {
+        Ok(comparison) => format_response(&comparison, query.format.unwrap_or(OutputFormat::Json)),
+        Err(e) => create_error_response(e),
+    }
+}
+
+#[derive(Deserialize)]
+struct ValueHistoryParams {
+    sha: String,
+    env: String,
+    path: String,
+}
+
+#[derive(Deserialize)]
+struct ValueHistoryQuery {
+    format: Option<OutputFormat>,
+    limit: Option<usize>,
+}
+
+/// Number of changes returned by the log route without a limit. The history walk stops once
+/// this many changes are found
+const DEFAULT_HISTORY_LIMIT: usize = 20;
+
+#[get("log/{sha}/{env}/{path}")]
+async fn get_value_history(
+    params: web::Path<ValueHistoryParams>,
+    query: web::Query<ValueHistoryQuery>,
+    state: web::Data<ServerState>,
+) -> HttpResponse {
+    let limit = query.limit.unwrap_or(DEFAULT_HISTORY_LIMIT);
+    let result = match task::spawn_blocking(move || {
+        let filter =
+            match hogan::config::build_env_regex(&params.env, Some(&state.environment_pattern)) {
+                Ok(filter) => filter,
+                Err(e) => {
+                    warn!("Incompatible env name: {} {:?}", params.env, e);
+                    state.environments_regex.clone()
+                }
+            };
+
+        history::value_history(
+            &state.config_dir,
+            &params.sha,
+            &params.env,
+            &params.path,
+            &filter,
+            Some(limit),
+        )
+    })
+    .await
+    {
+        Ok(r) => r,
+        Err(e) => {
+            warn!("Error joining when finding value history {:?}", e);
+            Err(e.into())
+        }
+    };
+
+    match result {
+        Ok(history) => format_response(&history, query.format.unwrap_or(OutputFormat::Json)),
+        Err(e) => create_error_response(e),
+    }
+}
+
+fn format_response<T: Serialize + std::fmt::Display>(
+    body: &T,
+    format: OutputFormat,
+) -> HttpResponse {
+    match format {
+        OutputFormat::Json => HttpResponse::Ok().json(body),
+        OutputFormat::Text => HttpResponse::Ok()
+            .content_type("text/plain")
+            .body(body.to_string()),
+    }
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct ShaResponse {
@@ -575,6 +864,34 @@ fn get_env(
     }
 }
 
+/// Partials are read from the configs repo's git objects so the working directory can stay
+/// wherever the last environment lookup left it
+fn get_partials(state: &ServerState, sha: &str) -> Result<Arc<PartialFiles>> {
+    let sha = format_sha(sha);
+    if let Some(partials) = state.partials.lock().get(sha) {
+        return Ok(partials.clone());
+    }
+
+    let partials = match state.config_dir.find_partials_at_revision(sha) {
+        Ok(partials) => partials,
+        Err(e) => {
+            debug!("Unable to read partials at {}, refreshing {:?}", sha, e);
+            let _write_lock = state.write_lock.lock();
+            state
+                .config_dir
+                .refresh(None, Some(sha), state.allow_fetch)?;
+            state.config_dir.find_partials_at_revision(sha)?
+        }
+    };
+
+    let partials = Arc::new(partials);
+    state
+        .partials
+        .lock()
+        .insert(sha.to_owned(), partials.clone());
+    Ok(partials)
+}
+
 fn check_env_listing_cache(state: &ServerState, sha: &str) -> Option<Arc<Vec<EnvDescription>>> {
     let sha = format_sha(sha);
     let mut cache = state.environment_listings.lock();
@@ -604,13 +921,13 @@ fn get_env_listing(
     sha: &str,
 ) -> Result<Arc<Vec<EnvDescription>>> {
     let sha = format_sha(sha);
-    if let Some(env) = check_env_listing_cache(state, &sha) {
+    if let Some(env) = check_env_listing_cache(state, sha) {
         Ok(env)
     } else {
         let _write_lock = state.write_lock.lock();
 
         //Check if the cache has what we are looking for again
-        if let Some(env) = check_env_listing_cache(state, &sha) {
+        if let Some(env) = check_env_listing_cache(state, sha) {
             return Ok(env);
         }
 
@@ -0,0 +1,367 @@
+use crate::app::cli::{build_handlebars, load_templates, render_output, write_rendered};
+use crate::app::config::{App, AppCommon, TransformOptions};
+use anyhow::Result;
+use hogan::config::{ConfigDir, Environment};
+use hogan::error::HoganError;
+use hogan::template::Template;
+use hogan::transform::PARTIALS_DIR;
+use notify::{DebouncedEvent, RecursiveMode, Watcher};
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::channel;
+use std::time::Duration;
+use walkdir::WalkDir;
+
+/// Changes are collected for this long, so saving several files renders once
+const DEBOUNCE_DELAY: Duration = Duration::from_millis(500);
+
+/// The sources rendering depends on, compared between runs to find what needs re-rendering
+#[derive(Default)]
+struct Snapshot {
+    templates: BTreeMap<PathBuf, Vec<u8>>,
+    partials: BTreeMap<PathBuf, Vec<u8>>,
+    /// Merged environments, so a change to an environment type or global config only affects
+    /// the environments whose merged config actually changed
+    environments: BTreeMap<String, Environment>,
+}
+
+impl Snapshot {
+    fn new(templates: &[Template], environments: &[Environment], partials: &[PathBuf]) -> Self {
+        Snapshot {
+            templates: templates
+                .iter()
+                .filter_map(|t| fs::read(&t.path).ok().map(|c| (t.path.clone(), c)))
+                .collect(),
+            partials: partials
+                .iter()
+                .flat_map(|dir| WalkDir::new(dir).into_iter().filter_map(|e| e.ok()))
+                .filter(|e| e.file_type().is_file())
+                .filter_map(|e| fs::read(e.path()).ok().map(|c| (e.into_path(), c)))
+                .collect(),
+            environments: environments
+                .iter()
+                .map(|e| (e.environment.clone(), e.clone()))
+                .collect(),
+        }
+    }
+
+    /// Whether the template's output for the environment may differ from the previous snapshot's
+    fn is_affected(&self, previous: &Snapshot, template: &Path, environment: &str) -> bool {
+        self.partials != previous.partials
+            || self.templates.get(template) != previous.templates.get(template)
+            || self.environments.get(environment) != previous.environments.get(environment)
+    }
+}
+
+/// Picks out the filesystem events which can change the rendered output, so rendered files,
+/// build directories and the like under the templates directory don't trigger a render
+struct WatchFilter {
+    templates_path: PathBuf,
+    templates_regex: Regex,
+    configs_path: PathBuf,
+    partials: Vec<PathBuf>,
+}
+
+impl WatchFilter {
+    fn is_relevant(&self, event: &DebouncedEvent) -> bool {
+        match event {
+            DebouncedEvent::Create(path)
+            | DebouncedEvent::Write(path)
+            | DebouncedEvent::Remove(path) => self.is_source(path),
+            DebouncedEvent::Rename(from, to) => self.is_source(from) || self.is_source(to),
+            // Events may have been missed, so render whatever changed
+            DebouncedEvent::Rescan | DebouncedEvent::Error(..) => true,
+            DebouncedEvent::NoticeWrite(_)
+            | DebouncedEvent::NoticeRemove(_)
+            | DebouncedEvent::Chmod(_) => false,
+        }
+    }
+
+    /// Whether the path is a template, config or partial
+    fn is_source(&self, path: &Path) -> bool {
+        let file_name = path.file_name().and_then(|f| f.to_str()).unwrap_or("");
+        self.partials.iter().any(|dir| path.starts_with(dir))
+            || (path.starts_with(&self.configs_path) && file_name.ends_with(".json"))
+            || (path.starts_with(&self.templates_path) && self.templates_regex.is_match(file_name))
+    }
+}
+
+/// Events are reported with absolute paths, so watched directories are compared in that form
+fn absolute(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_owned())
+}
+
+/// Renders everything once, then waits for changes to the templates and configs and re-renders
+/// the affected template and environment pairs. Errors are printed and watching continues
+pub fn watch(
+    templates_path: PathBuf,
+    environments_regex: Regex,
+    templates_regex: Regex,
+    common: AppCommon,
+    options: TransformOptions,
+) -> Result<()> {
+    let strict = common.strict;
+    let config_dir = ConfigDir::new(
+        common.configs_url,
+        &common.ssh_key,
+        common.native_git,
+        common.native_fetch,
+        common.native_clone,
+    )?;
+    if let ConfigDir::Git { .. } = config_dir {
+        return Err(HoganError::InvalidConfiguration {
+            param: "configs".to_string(),
+            msg: "Watching requires a local configs directory".to_string(),
+        }
+        .into());
+    }
+    let environments_filter = App::config_regex(&environments_regex)?;
+
+    let partials = Some(templates_path.join(PARTIALS_DIR))
+        .into_iter()
+        .chain(options.partials_path.clone())
+        .collect::<Vec<_>>();
+    let mut watched = vec![templates_path.clone(), config_dir.directory().to_owned()];
+    watched.extend(options.partials_path.clone());
+
+    let filter = WatchFilter {
+        templates_path: absolute(&templates_path),
+        templates_regex: templates_regex.clone(),
+        configs_path: absolute(config_dir.directory()),
+        partials: vec![absolute(&templates_path).join(PARTIALS_DIR)]
+            .into_iter()
+            .chain(options.partials_path.as_deref().map(absolute))
+            .collect(),
+    };
+    let (sender, events) = channel();
+    let mut watcher = notify::watcher(sender, DEBOUNCE_DELAY)?;
+    for path in &watched {
+        watcher.watch(path, RecursiveMode::Recursive)?;
+    }
+
+    let mut previous = Snapshot::default();
+    loop {
+        let run = || -> Result<Snapshot> {
+            let handlebars =
+                build_handlebars(strict, &templates_path, options.partials_path.as_deref())?;
+            let templates = load_templates(
+                &templates_path,
+                templates_regex.clone(),
+                options.output_pattern.as_ref(),
+                options.escape_by_extension,
+            )?;
+            let environments = config_dir.find(environments_filter.clone());
+            let config_sha = config_dir.head_sha();
+            let current = Snapshot::new(&templates, &environments, &partials);
+
+            let (mut rendered, mut failed) = (0, 0);
+            for environment in &environments {
+                for template in templates.iter().filter(|t| {
+                    t.applies_to(environment)
+                        && current.is_affected(&previous, &t.path, &environment.environment)
+                }) {
+                    match render_template(
+                        &handlebars,
+                        template,
+                        environment,
+                        config_sha.as_deref(),
+                        &options,
+                    ) {
+                        Ok(_) => rendered += 1,
+                        Err(e) => {
+                            failed += 1;
+                            eprintln!(
+                                "Error rendering {:?} for {}: {:#}",
+                                template.path, environment.environment, e
+                            );
+                        }
+                    }
+                }
+            }
+            println!("Rendered {} file(s) with {} error(s)", rendered, failed);
+
+            Ok(current)
+        };
+
+        // Keep the previous snapshot when nothing could be loaded so every change is retried
+        match run() {
+            Ok(current) => previous = current,
+            Err(e) => eprintln!("{:#}", e),
+        }
+
+        println!("Watching {:?} for changes", watched);
+        loop {
+            let event = events.recv().map_err(|_| HoganError::UnknownError {
+                msg: "Stopped receiving file changes".to_string(),
+            })?;
+            if filter.is_relevant(&event) {
+                debug!("Rendering after {:?}", event);
+                break;
+            }
+        }
+        // Changes made while rendering are picked up by the next render
+        while events.try_recv().is_ok() {}
+    }
+}
+
+fn render_template(
+    handlebars: &handlebars::Handlebars,
+    template: &Template,
+    environment: &Environment,
+    sha: Option<&str>,
+    options: &TransformOptions,
+) -> Result<()> {
+    let rendered = render_output(
+        handlebars,
+        template,
+        environment,
+        sha,
+        options.output_dir.as_deref(),
+    )?;
+
+    if options.diff {
+        if let Some(diff) = rendered.unified_diff()? {
+            println!("Template {}", template.path.display());
+            print!("{}", diff);
+        }
+    }
+
+    write_rendered(&rendered, options.ignore_existing)?;
+    println!(
+        "Updated {} for {}",
+        rendered.path.display(),
+        environment.environment
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fs_extra::dir;
+    use hogan::config::ConfigUrl;
+
+    #[test]
+    fn test_affected_pairs() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs_extra::copy_items(
+            &[
+                "tests/fixtures/configs",
+                "tests/fixtures/projects/templates",
+            ],
+            temp_dir.path(),
+            &dir::CopyOptions::new(),
+        )
+        .unwrap();
+        let templates_path = temp_dir.path().join("templates");
+        let configs_path = temp_dir.path().join("configs");
+
+        let config_dir = ConfigDir::new(
+            ConfigUrl::File {
+                path: configs_path.clone(),
+            },
+            Path::new(""),
+            false,
+            false,
+            false,
+        )
+        .unwrap();
+        let filter = App::config_regex(&App::parse_regex(".+").unwrap()).unwrap();
+        let snapshot = || {
+            let templates_filter =
+                App::parse_regex("^[^.]*(\\w+\\.)*template([-.].+)?\\.(config|ya?ml|properties)")
+                    .unwrap();
+            let templates = load_templates(&templates_path, templates_filter, None, false).unwrap();
+            let environments = config_dir.find(filter.clone());
+            (
+                Snapshot::new(&templates, &environments, &[]),
+                templates,
+                environments,
+            )
+        };
+        let affected = |current: &Snapshot,
+                        previous: &Snapshot,
+                        templates: &[Template],
+                        environments: &[Environment]| {
+            let mut pairs = Vec::new();
+            for environment in environments {
+                for template in templates {
+                    if current.is_affected(previous, &template.path, &environment.environment) {
+                        pairs.push((
+                            template
+                                .path
+                                .strip_prefix(&templates_path)
+                                .unwrap()
+                                .to_string_lossy()
+                                .into_owned(),
+                            environment.environment.clone(),
+                        ));
+                    }
+                }
+            }
+            pairs.sort();
+            pairs
+        };
+
+        let (first, templates, environments) = snapshot();
+        assert_eq!(
+            affected(&first, &Snapshot::default(), &templates, &environments).len(),
+            24
+        );
+
+        let (unchanged, templates, environments) = snapshot();
+        assert!(affected(&unchanged, &first, &templates, &environments).is_empty());
+
+        fs::write(
+            configs_path.join("envTypes/alpha.json"),
+            r#"{"EnvironmentType": "alpha", "ConfigData": {"EnvironmentType": "beta"}}"#,
+        )
+        .unwrap();
+        let (type_changed, templates, environments) = snapshot();
+        let pairs = affected(&type_changed, &unchanged, &templates, &environments);
+        assert_eq!(pairs.len(), 6);
+        assert!(pairs.iter().all(|(_, env)| env == "ENVTYPE"));
+
+        fs::write(
+            templates_path.join("project-2/Web.template.config"),
+            "{{Region.Key}}",
+        )
+        .unwrap();
+        let (template_changed, templates, environments) = snapshot();
+        let pairs = affected(&template_changed, &type_changed, &templates, &environments);
+        assert_eq!(pairs.len(), 4);
+        assert!(pairs
+            .iter()
+            .all(|(template, _)| template == "project-2/Web.template.config"));
+    }
+
+    #[test]
+    fn test_watch_filter() {
+        let filter = WatchFilter {
+            templates_path: PathBuf::from("/repo"),
+            templates_regex: App::parse_regex("template.*\\.config").unwrap(),
+            configs_path: PathBuf::from("/repo/configs"),
+            partials: vec![PathBuf::from("/repo/_partials"), PathBuf::from("/shared")],
+        };
+        let write = |path: &str| DebouncedEvent::Write(PathBuf::from(path));
+
+        assert!(filter.is_relevant(&write("/repo/app/Web.template.config")));
+        assert!(filter.is_relevant(&write("/repo/configs/envTypes/alpha.json")));
+        assert!(filter.is_relevant(&write("/repo/_partials/header.hbs")));
+        assert!(filter.is_relevant(&write("/shared/footer.hbs")));
+        assert!(filter.is_relevant(&DebouncedEvent::Rename(
+            PathBuf::from("/repo/app/Web.template.config"),
+            PathBuf::from("/repo/app/Web.bak"),
+        )));
+        assert!(filter.is_relevant(&DebouncedEvent::Rescan));
+
+        assert!(!filter.is_relevant(&write("/repo/app/Web.config")));
+        assert!(!filter.is_relevant(&write("/repo/target/debug/build.log")));
+        assert!(!filter.is_relevant(&write("/repo/configs/.git/index")));
+        assert!(!filter.is_relevant(&DebouncedEvent::Chmod(PathBuf::from(
+            "/repo/app/Web.template.config"
+        ))));
+    }
+}
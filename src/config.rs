@@ -1,6 +1,7 @@
 use crate::error::HoganError;
 use crate::find_file_paths;
 use crate::git;
+use crate::transform::PARTIALS_DIR;
 use anyhow::{Context, Result};
 use json_patch::merge;
 use regex::Regex;
@@ -29,7 +30,7 @@ impl FromStr for ConfigUrl {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        match Url::parse(&s) {
+        match Url::parse(s) {
             Ok(url) => {
                 if url.scheme() == "file" {
                     Ok(ConfigUrl::File {
@@ -135,12 +136,7 @@ impl ConfigDir {
                     git::ext_clone(&url, temp_dir.path())?;
                     git::build_repo(temp_dir.path().to_str().unwrap())?
                 } else {
-                    git::clone(
-                        &url,
-                        branch.as_deref(),
-                        temp_dir.path(),
-                        Some(&ssh_key_path),
-                    )?
+                    git::clone(&url, branch.as_deref(), temp_dir.path(), Some(ssh_key_path))?
                 };
 
                 let head_sha = git::get_head_sha(&git_repo)?;
@@ -219,6 +215,24 @@ impl ConfigDir {
         }
     }
 
+    /// The current head SHA of the configs repository. A file config only has one when its
+    /// directory is the root of a repository without uncommitted changes, so the SHA of an
+    /// enclosing repository or of edited configs is never reported
+    pub fn head_sha(&self) -> Option<String> {
+        match self {
+            ConfigDir::File { directory } => {
+                let repo = git::open_repo(directory).ok()?;
+                match git::has_uncommitted_changes(&repo) {
+                    Ok(false) => git::get_head_sha(&repo).ok(),
+                    _ => None,
+                }
+            }
+            ConfigDir::Git { directory, .. } => git::build_repo(directory.to_str()?)
+                .and_then(|repo| git::get_head_sha(&repo))
+                .ok(),
+        }
+    }
+
     pub fn refresh(
         &self,
         remote: Option<&str>,
@@ -254,36 +268,73 @@ impl ConfigDir {
     }
 
     pub fn find(&self, filter: Regex) -> Vec<Environment> {
-        fn find_env_type_data<'a>(types: &'a [EnvironmentType], name: &str) -> &'a Value {
-            types
-                .iter()
-                .find(|e| e.environment_type == name)
-                .map(|env| &env.config_data)
-                .unwrap_or(&Value::Null)
-        }
+        let environment_types =
+            ConfigDir::find_environment_types(self).collect::<Vec<EnvironmentType>>();
+
+        merge_environments(
+            &environment_types,
+            ConfigDir::find_environments(self, filter),
+        )
+    }
 
+    /// Finds environments along with the unmerged configs their config data was merged from
+    pub fn find_with_layers(&self, filter: Regex) -> Vec<(Environment, ConfigLayers)> {
         let environment_types =
             ConfigDir::find_environment_types(self).collect::<Vec<EnvironmentType>>();
         let global = find_env_type_data(&environment_types, "global");
 
         ConfigDir::find_environments(self, filter)
-            .map(|mut environment| {
-                let parent = if let Some(ref env_type_name) = environment.environment_type {
-                    find_env_type_data(&environment_types, env_type_name)
-                } else {
-                    &Value::Null
+            .map(|environment| {
+                let layers = ConfigLayers {
+                    global: global.clone(),
+                    environment_type: environment
+                        .environment_type
+                        .as_ref()
+                        .map(|t| find_env_type_data(&environment_types, t).clone())
+                        .unwrap_or(Value::Null),
+                    environment: environment.config_data.clone(),
                 };
-
-                let mut config_data = global.clone(); // Start with global
-                merge(&mut config_data, &parent); // Merge in an env type
-                merge(&mut config_data, &environment.config_data); // Merge with the actual config
-
-                environment.config_data = config_data;
-                environment
+                let merged =
+                    merge_environments(&environment_types, std::iter::once(environment)).remove(0);
+                (merged, layers)
             })
             .collect()
     }
 
+    /// Finds environments as they existed at the given revision, reading directly from the git
+    /// object database instead of the working directory
+    pub fn find_at_revision(&self, revision: &str, filter: Regex) -> Result<Vec<Environment>> {
+        let files = self
+            .read_files_at_revision(revision, Path::new(""))
+            .with_context(|| format!("Finding environments at {}", revision))?;
+
+        Ok(environments_from_files(&files, &filter))
+    }
+
+    /// Reads the files of the partials directory at the given revision, with paths relative to
+    /// the partials directory. Returns no files if the directory does not exist
+    pub fn find_partials_at_revision(&self, revision: &str) -> Result<Vec<(PathBuf, Vec<u8>)>> {
+        self.read_files_at_revision(revision, Path::new(PARTIALS_DIR))
+            .with_context(|| format!("Finding partials at {}", revision))
+    }
+
+    fn read_files_at_revision(
+        &self,
+        revision: &str,
+        path: &Path,
+    ) -> Result<Vec<(PathBuf, Vec<u8>)>> {
+        let git_repo = git::build_repo(self.directory().to_str().unwrap())?;
+        let commit = git::find_commit(&git_repo, revision)?;
+        let internal_path = git::repo_relative_path(&git_repo, self.directory())?;
+
+        let files = match git::find_subtree(&git_repo, &commit, &internal_path.join(path))? {
+            Some(tree) => git::read_tree_files(&git_repo, &tree)?,
+            None => Vec::new(),
+        };
+
+        Ok(files)
+    }
+
     fn find_environments(&self, filter: Regex) -> Box<dyn Iterator<Item = Environment>> {
         Box::new(
             find_file_paths(self.directory(), filter)
@@ -302,7 +353,7 @@ impl ConfigDir {
                 .filter_map(|e| {
                     let path = e.path();
                     let env_type = path.file_stem().unwrap().to_string_lossy().into_owned();
-                    File::open(&path)
+                    File::open(path)
                         .ok()
                         .and_then(|f| serde_json::from_reader(f).ok())
                         .and_then(|c: Config| c.into_environment_type())
@@ -357,7 +408,7 @@ impl ConfigDir {
                 ..
             } => {
                 if *native_git {
-                    git::ext_maintenance(&directory.as_path())
+                    git::ext_maintenance(directory.as_path())
                         .with_context(|| "Performing Maintenance")?;
                 }
                 Ok(())
@@ -380,7 +431,7 @@ impl ConfigDir {
                 ..
             } => {
                 if *native_git && *native_fetch {
-                    git::ext_fetch(&directory.as_path(), remote_name)
+                    git::ext_fetch(directory.as_path(), remote_name)
                         .with_context(|| "Fetching git repo")?;
                     Ok(())
                 } else {
@@ -418,7 +469,7 @@ impl Config {
     }
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 #[serde(rename_all = "PascalCase")]
 pub struct Environment {
     pub environment: String,
@@ -426,6 +477,14 @@ pub struct Environment {
     pub config_data: Value,
 }
 
+/// The config data an environment is merged from, in merge order
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigLayers {
+    pub global: Value,
+    pub environment_type: Value,
+    pub environment: Value,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "PascalCase")]
 struct EnvironmentType {
@@ -433,6 +492,68 @@ struct EnvironmentType {
     config_data: Value,
 }
 
+fn find_env_type_data<'a>(types: &'a [EnvironmentType], name: &str) -> &'a Value {
+    types
+        .iter()
+        .find(|e| e.environment_type == name)
+        .map(|env| &env.config_data)
+        .unwrap_or(&Value::Null)
+}
+
+fn merge_environments(
+    environment_types: &[EnvironmentType],
+    environments: impl Iterator<Item = Environment>,
+) -> Vec<Environment> {
+    let global = find_env_type_data(environment_types, "global");
+
+    environments
+        .map(|mut environment| {
+            let parent = if let Some(ref env_type_name) = environment.environment_type {
+                find_env_type_data(environment_types, env_type_name)
+            } else {
+                &Value::Null
+            };
+
+            let mut config_data = global.clone(); // Start with global
+            merge(&mut config_data, parent); // Merge in an env type
+            merge(&mut config_data, &environment.config_data); // Merge with the actual config
+
+            environment.config_data = config_data;
+            environment
+        })
+        .collect()
+}
+
+/// Builds merged environments from raw config files, applying the same rules as a directory
+/// search: environments are filtered on file name, environment types are named by file stem
+pub(crate) fn environments_from_files(
+    files: &[(PathBuf, Vec<u8>)],
+    filter: &Regex,
+) -> Vec<Environment> {
+    let mut environments = Vec::new();
+    let mut environment_types = Vec::new();
+
+    for (path, contents) in files {
+        match serde_json::from_slice(contents) {
+            Ok(Config::Environment(environment)) => {
+                let file_name = path.file_name().and_then(|f| f.to_str()).unwrap_or("");
+                if filter.is_match(file_name) {
+                    environments.push(environment);
+                }
+            }
+            Ok(Config::EnvironmentType(mut environment_type)) => {
+                if let Some(stem) = path.file_stem() {
+                    environment_type.environment_type = stem.to_string_lossy().into_owned();
+                }
+                environment_types.push(environment_type);
+            }
+            Err(_) => {}
+        }
+    }
+
+    merge_environments(&environment_types, environments.into_iter())
+}
+
 pub fn build_regex(pattern: &str) -> Result<Regex> {
     RegexBuilder::new(pattern)
         .case_insensitive(true)
@@ -460,9 +581,10 @@ pub fn build_env_regex(env: &str, base_pattern: Option<&str>) -> Result<Regex> {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
-    use std::path::Path;
+    pub(crate) use crate::test_repo::{commit_all, init_config_repo};
+    use std::fs;
 
     #[test]
     fn test_github_url() {
@@ -652,6 +774,30 @@ mod tests {
         assert_eq!(environments.len(), 4)
     }
 
+    #[test]
+    fn test_find_with_layers() {
+        let config_dir = ConfigDir::new(
+            "file://./tests/fixtures/configs".parse().unwrap(),
+            Path::new(""),
+            true,
+            true,
+            true,
+        )
+        .unwrap();
+        let environments =
+            config_dir.find_with_layers(build_regex("config\\.ENVTYPE\\.json$").unwrap());
+        assert_eq!(environments.len(), 1);
+
+        let (environment, layers) = &environments[0];
+        assert_eq!(layers.global, Value::Null);
+        assert_eq!(
+            layers.environment_type,
+            serde_json::json!({"EnvironmentType": "alpha"})
+        );
+        assert_eq!(layers.environment["Region"]["Key"], "ENVTYPE");
+        assert_eq!(environment.config_data["EnvironmentType"], "alpha");
+    }
+
     #[test]
     fn test_find_subset_configs() {
         let config_dir = ConfigDir::new(
@@ -665,4 +811,113 @@ mod tests {
         let environments = config_dir.find(build_regex(r#"config\.test\d?\.json"#).unwrap());
         assert_eq!(environments.len(), 2)
     }
+
+    #[test]
+    fn test_find_at_revision() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo = init_config_repo(temp_dir.path());
+        let first_sha = git::get_head_sha(&repo).unwrap();
+
+        let config_path = temp_dir.path().join("configs/config.TEST.json");
+        let updated = fs::read_to_string(&config_path)
+            .unwrap()
+            .replace(r#""Key": "TEST""#, r#""Key": "UPDATED""#);
+        fs::write(&config_path, updated).unwrap();
+        fs::remove_file(temp_dir.path().join("configs/config.EMPTY.json")).unwrap();
+        let second_sha = commit_all(&repo, "Update TEST");
+
+        let config_dir = ConfigDir::new(
+            ConfigUrl::File {
+                path: temp_dir.path().join("configs"),
+            },
+            Path::new(""),
+            false,
+            false,
+            false,
+        )
+        .unwrap();
+        let filter = build_regex("config\\..+\\.json$").unwrap();
+
+        let before = config_dir
+            .find_at_revision(&first_sha, filter.clone())
+            .unwrap();
+        assert_eq!(before.len(), 4);
+        let before_test = before.iter().find(|e| e.environment == "TEST").unwrap();
+        assert_eq!(before_test.config_data["Region"]["Key"], "TEST");
+
+        let after = config_dir.find_at_revision(&second_sha, filter).unwrap();
+        assert_eq!(after.len(), 3);
+        let after_test = after.iter().find(|e| e.environment == "TEST").unwrap();
+        assert_eq!(after_test.config_data["Region"]["Key"], "UPDATED");
+
+        let envtype = after.iter().find(|e| e.environment == "ENVTYPE").unwrap();
+        assert_eq!(envtype.config_data["EnvironmentType"], "alpha");
+    }
+
+    #[test]
+    fn test_head_sha() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo = init_config_repo(temp_dir.path());
+
+        // A directory inside the repository doesn't report the enclosing repository's head
+        let nested = ConfigDir::File {
+            directory: temp_dir.path().join("configs"),
+        };
+        assert_eq!(nested.head_sha(), None);
+
+        let root = ConfigDir::File {
+            directory: temp_dir.path().to_owned(),
+        };
+        assert_eq!(root.head_sha(), Some(git::get_head_sha(&repo).unwrap()));
+
+        fs::write(temp_dir.path().join("configs/config.NEW.json"), "{}").unwrap();
+        assert_eq!(root.head_sha(), None);
+    }
+
+    #[test]
+    fn test_find_partials_at_revision() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo = init_config_repo(temp_dir.path());
+        let config_dir = ConfigDir::File {
+            directory: temp_dir.path().join("configs"),
+        };
+
+        let without_partials = git::get_head_sha(&repo).unwrap();
+        let partials_path = temp_dir.path().join("configs/_partials/db");
+        fs::create_dir_all(&partials_path).unwrap();
+        fs::write(partials_path.join("sql.hbs"), "{{DB.Endpoint}}").unwrap();
+        let with_partials = commit_all(&repo, "Add partials");
+
+        assert!(config_dir
+            .find_partials_at_revision(&without_partials)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            config_dir
+                .find_partials_at_revision(&with_partials)
+                .unwrap(),
+            vec![(PathBuf::from("db/sql.hbs"), b"{{DB.Endpoint}}".to_vec())]
+        );
+    }
+
+    #[test]
+    fn test_find_at_unknown_revision() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        init_config_repo(temp_dir.path());
+
+        let config_dir = ConfigDir::new(
+            ConfigUrl::File {
+                path: temp_dir.path().join("configs"),
+            },
+            Path::new(""),
+            false,
+            false,
+            false,
+        )
+        .unwrap();
+
+        assert!(config_dir
+            .find_at_revision("deadbeef", build_regex(".+").unwrap())
+            .is_err());
+    }
 }
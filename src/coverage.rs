@@ -0,0 +1,280 @@
+use crate::config::{ConfigLayers, Environment};
+use crate::diff::join_path;
+use crate::template::Template;
+use crate::vars::{Reference, ANY_ELEMENT};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Where a config key is defined and whether any template uses it
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct KeyCoverage {
+    /// Leaf path of the key, with array indexes written as *
+    pub path: String,
+    /// Defined in the global config
+    pub global: bool,
+    /// Environment types defining the key
+    pub environment_types: Vec<String>,
+    /// Environments defining the key in their own config
+    pub environments: Vec<String>,
+    /// Referenced by a template rendered for an environment which has the key
+    pub used: bool,
+}
+
+/// Lists the leaf paths of a config, with array indexes written as * so every element of an
+/// array shares its paths. Empty objects and arrays are leaves
+pub fn leaf_paths(value: &Value) -> BTreeSet<String> {
+    fn collect(prefix: &str, value: &Value, paths: &mut BTreeSet<String>) {
+        match value {
+            Value::Object(map) if !map.is_empty() => {
+                for (key, value) in map {
+                    collect(&join_path(prefix, key), value, paths);
+                }
+            }
+            Value::Array(list) if !list.is_empty() => {
+                for value in list {
+                    collect(&join_path(prefix, ANY_ELEMENT), value, paths);
+                }
+            }
+            _ => {
+                if !prefix.is_empty() {
+                    paths.insert(prefix.to_owned());
+                }
+            }
+        }
+    }
+
+    let mut paths = BTreeSet::new();
+    collect("", value, &mut paths);
+    paths
+}
+
+/// Whether the leaf path exists in the config, with * matching any array element
+fn defines(value: &Value, path: &str) -> bool {
+    let mut segments = path.splitn(2, '.');
+    let segment = segments.next().unwrap_or("");
+    let rest = segments.next();
+
+    let children: Vec<&Value> = match (value, segment) {
+        (Value::Array(list), ANY_ELEMENT) => list.iter().collect(),
+        (Value::Object(map), _) => map.get(segment).into_iter().collect(),
+        _ => Vec::new(),
+    };
+    match rest {
+        Some(rest) => children.iter().any(|child| defines(child, rest)),
+        None => !children.is_empty(),
+    }
+}
+
+/// Whether the reference uses the leaf path. References to a parent of the path only use it when
+/// their whole value is rendered or passed to a helper, not when they are iterated or tested
+pub fn covers(reference: &Reference, path: &str) -> bool {
+    let mut referenced = reference.path.split('.');
+    let mut segments = path.split('.');
+
+    loop {
+        match (referenced.next(), segments.next()) {
+            (Some(r), Some(s)) => {
+                let matches =
+                    r == s || r == ANY_ELEMENT || (s == ANY_ELEMENT && r.parse::<usize>().is_ok());
+                if !matches {
+                    return false;
+                }
+            }
+            (None, None) => return true,
+            (None, Some(_)) => return reference.value_used,
+            (Some(_), None) => return false,
+        }
+    }
+}
+
+/// Finds the leaf keys of every environment's merged config, where each is defined and whether a
+/// template which applies to the environment references it
+pub fn coverage(
+    environments: &[(Environment, ConfigLayers)],
+    templates: &[(Template, Vec<Reference>)],
+) -> Vec<KeyCoverage> {
+    let mut keys: BTreeMap<String, KeyCoverage> = BTreeMap::new();
+
+    for (environment, layers) in environments {
+        let references = templates
+            .iter()
+            .filter(|(template, _)| template.applies_to(environment))
+            .flat_map(|(_, references)| references)
+            .collect::<Vec<_>>();
+
+        for path in leaf_paths(&environment.config_data) {
+            let key = keys.entry(path.clone()).or_insert_with(|| KeyCoverage {
+                path: path.clone(),
+                global: false,
+                environment_types: Vec::new(),
+                environments: Vec::new(),
+                used: false,
+            });
+
+            key.global |= defines(&layers.global, &path);
+            if let Some(ref environment_type) = environment.environment_type {
+                if defines(&layers.environment_type, &path)
+                    && !key.environment_types.contains(environment_type)
+                {
+                    key.environment_types.push(environment_type.clone());
+                }
+            }
+            if defines(&layers.environment, &path) {
+                key.environments.push(environment.environment.clone());
+            }
+            key.used |= references.iter().any(|r| covers(r, &path));
+        }
+    }
+
+    keys.into_values()
+        .map(|mut key| {
+            key.environment_types.sort();
+            key.environments.sort();
+            key
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+
+    fn reference(path: &str, value_used: bool) -> Reference {
+        Reference {
+            path: path.to_string(),
+            partial: None,
+            line: 1,
+            column: 1,
+            optional: false,
+            value_used,
+        }
+    }
+
+    #[test]
+    fn test_leaf_paths() {
+        let config = serde_json::json!({
+            "Region": {"Key": "TEST"},
+            "Servers": [{"Endpoint": "a", "Port": 1}, {"Endpoint": "b", "Tls": true}],
+            "Empty": {},
+        });
+
+        assert_eq!(
+            leaf_paths(&config).into_iter().collect::<Vec<_>>(),
+            vec![
+                "Empty",
+                "Region.Key",
+                "Servers.*.Endpoint",
+                "Servers.*.Port",
+                "Servers.*.Tls"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_covers() {
+        assert!(covers(&reference("Region.Key", true), "Region.Key"));
+        assert!(covers(&reference("Servers.*.Port", true), "Servers.*.Port"));
+        assert!(covers(&reference("Servers.0.Port", true), "Servers.*.Port"));
+        assert!(covers(
+            &reference("Services.*.url", true),
+            "Services.api.url"
+        ));
+        assert!(covers(&reference("Region", true), "Region.Key"));
+        assert!(!covers(&reference("Servers", false), "Servers.*.Port"));
+        assert!(!covers(&reference("Region.Key.Nested", true), "Region.Key"));
+        assert!(!covers(&reference("Region.Name", true), "Region.Key"));
+    }
+
+    #[test]
+    fn test_coverage() {
+        let environment = |name: &str, config_data| Environment {
+            environment: name.to_string(),
+            environment_type: Some("alpha".to_string()),
+            config_data,
+        };
+        let layers = |environment| ConfigLayers {
+            global: serde_json::json!({"Region": {"Key": "global", "Unused": true}}),
+            environment_type: serde_json::json!({"Region": {"Name": "alpha"}}),
+            environment,
+        };
+        let environments = vec![
+            (
+                environment(
+                    "TEST",
+                    serde_json::json!({"Region": {"Key": "TEST", "Name": "alpha", "Unused": true}}),
+                ),
+                layers(serde_json::json!({"Region": {"Key": "TEST"}})),
+            ),
+            (
+                environment(
+                    "PROD",
+                    serde_json::json!({"Region": {"Key": "global", "Name": "alpha", "Unused": true}, "Legacy": 1}),
+                ),
+                layers(serde_json::json!({"Legacy": 1})),
+            ),
+        ];
+        let template = Template::new(
+            Path::new("templates"),
+            PathBuf::from("templates/Web.template.config"),
+            "{{Region.Key}} {{Region.Name}} {{Legacy}}",
+        )
+        .unwrap();
+        let staging_only = Template::new(
+            Path::new("templates"),
+            PathBuf::from("templates/Staging.template.config"),
+            "---hogan\nenvironments: STAGING\n---\n{{Region.Unused}}",
+        )
+        .unwrap();
+
+        let keys = coverage(
+            &environments,
+            &[
+                (
+                    template,
+                    vec![
+                        reference("Region.Key", true),
+                        reference("Region.Name", true),
+                        reference("Legacy", true),
+                    ],
+                ),
+                (staging_only, vec![reference("Region.Unused", true)]),
+            ],
+        );
+
+        assert_eq!(
+            keys,
+            vec![
+                KeyCoverage {
+                    path: "Legacy".to_string(),
+                    global: false,
+                    environment_types: vec![],
+                    environments: vec!["PROD".to_string()],
+                    used: true,
+                },
+                KeyCoverage {
+                    path: "Region.Key".to_string(),
+                    global: true,
+                    environment_types: vec![],
+                    environments: vec!["TEST".to_string()],
+                    used: true,
+                },
+                KeyCoverage {
+                    path: "Region.Name".to_string(),
+                    global: false,
+                    environment_types: vec!["alpha".to_string()],
+                    environments: vec![],
+                    used: true,
+                },
+                KeyCoverage {
+                    path: "Region.Unused".to_string(),
+                    global: true,
+                    environment_types: vec![],
+                    environments: vec![],
+                    used: false,
+                },
+            ]
+        );
+    }
+}
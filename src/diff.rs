@@ -0,0 +1,422 @@
+use crate::config::{build_regex, Environment};
+use regex::Regex;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fmt;
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(tag = "Type")]
+pub enum Change {
+    #[serde(rename_all = "PascalCase")]
+    Added { path: String, value: Value },
+    #[serde(rename_all = "PascalCase")]
+    Removed { path: String, value: Value },
+    #[serde(rename_all = "PascalCase")]
+    Changed {
+        path: String,
+        from: Value,
+        to: Value,
+    },
+}
+
+impl Change {
+    pub fn path(&self) -> &str {
+        match self {
+            Change::Added { path, .. } => path,
+            Change::Removed { path, .. } => path,
+            Change::Changed { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Change::Added { path, value } => write!(f, "+ {}: {}", path, value),
+            Change::Removed { path, value } => write!(f, "- {}: {}", path, value),
+            Change::Changed { path, from, to } => write!(f, "~ {}: {} -> {}", path, from, to),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct EnvironmentDiff {
+    pub environment: String,
+    pub changes: Vec<Change>,
+}
+
+impl fmt::Display for EnvironmentDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.changes.is_empty() {
+            return writeln!(f, "{}: no changes", self.environment);
+        }
+
+        writeln!(f, "{}: {} change(s)", self.environment, self.changes.len())?;
+        for change in &self.changes {
+            writeln!(f, "  {}", change)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct EnvironmentComparison {
+    pub from: String,
+    pub to: String,
+    pub changes: Vec<Change>,
+    /// Number of differences hidden because they only differed by the environment name
+    pub collapsed: usize,
+}
+
+impl fmt::Display for EnvironmentComparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} -> {}: {} difference(s)",
+            self.from,
+            self.to,
+            self.changes.len()
+        )?;
+        for change in &self.changes {
+            writeln!(f, "  {}", change)?;
+        }
+        if self.collapsed > 0 {
+            writeln!(
+                f,
+                "  ({} difference(s) only in the environment name collapsed)",
+                self.collapsed
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// Paths are dot separated object keys, with array elements addressed by their index
+// e.g. Memcache.Servers.0.Endpoint
+pub fn join_path(parent: &str, segment: &str) -> String {
+    if parent.is_empty() {
+        segment.to_owned()
+    } else {
+        format!("{}.{}", parent, segment)
+    }
+}
+
+pub fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .filter(|segment| !segment.is_empty())
+        .try_fold(value, |current, segment| match current {
+            Value::Object(map) => map.get(segment),
+            Value::Array(list) => segment.parse::<usize>().ok().and_then(|i| list.get(i)),
+            _ => None,
+        })
+}
+
+pub fn diff(from: &Value, to: &Value) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_at("", Some(from), Some(to), &mut changes);
+    changes
+}
+
+fn diff_at(path: &str, from: Option<&Value>, to: Option<&Value>, changes: &mut Vec<Change>) {
+    // A merged config never contains nulls, so treat them as missing keys
+    let from = from.filter(|v| !v.is_null());
+    let to = to.filter(|v| !v.is_null());
+
+    match (from, to) {
+        (Some(Value::Object(from_map)), Some(Value::Object(to_map))) => {
+            for (key, from_value) in from_map {
+                diff_at(
+                    &join_path(path, key),
+                    Some(from_value),
+                    to_map.get(key),
+                    changes,
+                );
+            }
+            for (key, to_value) in to_map.iter().filter(|(k, _)| !from_map.contains_key(*k)) {
+                diff_at(&join_path(path, key), None, Some(to_value), changes);
+            }
+        }
+        (Some(Value::Array(from_list)), Some(Value::Array(to_list))) => {
+            for i in 0..from_list.len().max(to_list.len()) {
+                diff_at(
+                    &join_path(path, &i.to_string()),
+                    from_list.get(i),
+                    to_list.get(i),
+                    changes,
+                );
+            }
+        }
+        (Some(Value::Object(from_map)), None) if path.is_empty() => {
+            for (key, value) in from_map {
+                diff_at(&join_path(path, key), Some(value), None, changes);
+            }
+        }
+        (None, Some(Value::Object(to_map))) if path.is_empty() => {
+            for (key, value) in to_map {
+                diff_at(&join_path(path, key), None, Some(value), changes);
+            }
+        }
+        (Some(from), Some(to)) => {
+            if from != to {
+                changes.push(Change::Changed {
+                    path: path.to_owned(),
+                    from: from.clone(),
+                    to: to.clone(),
+                })
+            }
+        }
+        (Some(from), None) => changes.push(Change::Removed {
+            path: path.to_owned(),
+            value: from.clone(),
+        }),
+        (None, Some(to)) => changes.push(Change::Added {
+            path: path.to_owned(),
+            value: to.clone(),
+        }),
+        (None, None) => {}
+    }
+}
+
+/// Diffs environments matched by name, an environment missing from either side is treated as empty
+pub fn diff_environments(from: &[Environment], to: &[Environment]) -> Vec<EnvironmentDiff> {
+    fn find_config<'a>(environments: &'a [Environment], name: &str) -> &'a Value {
+        environments
+            .iter()
+            .find(|e| e.environment == name)
+            .map(|e| &e.config_data)
+            .unwrap_or(&Value::Null)
+    }
+
+    from.iter()
+        .chain(to.iter())
+        .map(|e| e.environment.as_str())
+        .collect::<BTreeSet<&str>>()
+        .into_iter()
+        .map(|name| EnvironmentDiff {
+            environment: name.to_owned(),
+            changes: diff(find_config(from, name), find_config(to, name)),
+        })
+        .collect()
+}
+
+/// Structurally compares the merged configs of two environments. When collapsing, values that
+/// are equal once each environment's name is replaced by a placeholder are not reported
+pub fn compare_environments(
+    from: &Environment,
+    to: &Environment,
+    collapse_env_names: bool,
+) -> EnvironmentComparison {
+    let mut changes = diff(&from.config_data, &to.config_data);
+    let total = changes.len();
+
+    if collapse_env_names {
+        if let (Ok(from_name), Ok(to_name)) = (
+            build_regex(&regex::escape(&from.environment)),
+            build_regex(&regex::escape(&to.environment)),
+        ) {
+            changes.retain(|change| match change {
+                Change::Changed {
+                    from: from_value,
+                    to: to_value,
+                    ..
+                } => mask_env_name(from_value, &from_name) != mask_env_name(to_value, &to_name),
+                _ => true,
+            });
+        }
+    }
+
+    EnvironmentComparison {
+        from: from.environment.to_owned(),
+        to: to.environment.to_owned(),
+        collapsed: total - changes.len(),
+        changes,
+    }
+}
+
+fn mask_env_name(value: &Value, env_name: &Regex) -> Value {
+    match value {
+        Value::String(s) => Value::String(env_name.replace_all(s, "{env}").into_owned()),
+        Value::Array(list) => {
+            Value::Array(list.iter().map(|v| mask_env_name(v, env_name)).collect())
+        }
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| (k.to_owned(), mask_env_name(v, env_name)))
+                .collect(),
+        ),
+        _ => value.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_identical_configs() {
+        let config = json!({"a": 1, "b": {"c": [1, 2]}});
+        assert!(diff(&config, &config).is_empty());
+    }
+
+    #[test]
+    fn test_added_removed_changed() {
+        let from = json!({"a": 1, "b": {"c": "x", "d": true}, "e": [1, 2, 3]});
+        let to = json!({"a": 2, "b": {"c": "x", "f": null, "g": {"h": 1}}, "e": [1, 3]});
+
+        assert_eq!(
+            diff(&from, &to),
+            vec![
+                Change::Changed {
+                    path: "a".to_string(),
+                    from: json!(1),
+                    to: json!(2)
+                },
+                Change::Removed {
+                    path: "b.d".to_string(),
+                    value: json!(true)
+                },
+                Change::Added {
+                    path: "b.g".to_string(),
+                    value: json!({"h": 1})
+                },
+                Change::Changed {
+                    path: "e.1".to_string(),
+                    from: json!(2),
+                    to: json!(3)
+                },
+                Change::Removed {
+                    path: "e.2".to_string(),
+                    value: json!(3)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_missing_environment() {
+        let to = json!({"a": 1, "b": {"c": 2}});
+        assert_eq!(
+            diff(&Value::Null, &to),
+            vec![
+                Change::Added {
+                    path: "a".to_string(),
+                    value: json!(1)
+                },
+                Change::Added {
+                    path: "b".to_string(),
+                    value: json!({"c": 2})
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_type_change() {
+        let from = json!({"a": {"b": 1}});
+        let to = json!({"a": "b"});
+        assert_eq!(
+            diff(&from, &to),
+            vec![Change::Changed {
+                path: "a".to_string(),
+                from: json!({"b": 1}),
+                to: json!("b")
+            }]
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let changes = diff(&json!({"a": 1, "b": 2}), &json!({"a": 3, "c": "x"}));
+        let rendered = changes
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(rendered, vec!["~ a: 1 -> 3", "- b: 2", "+ c: \"x\""]);
+    }
+
+    #[test]
+    fn test_diff_environments() {
+        let environment = |name: &str, config_data: Value| Environment {
+            environment: name.to_owned(),
+            environment_type: None,
+            config_data,
+        };
+
+        let from = vec![
+            environment("TEST", json!({"a": 1})),
+            environment("OLD", json!({"a": 1})),
+        ];
+        let to = vec![
+            environment("TEST", json!({"a": 1})),
+            environment("NEW", json!({"a": 2})),
+        ];
+
+        let diffs = diff_environments(&from, &to);
+        assert_eq!(
+            diffs
+                .iter()
+                .map(|d| (d.environment.as_str(), d.changes.len()))
+                .collect::<Vec<_>>(),
+            vec![("NEW", 1), ("OLD", 1), ("TEST", 0)]
+        );
+    }
+
+    #[test]
+    fn test_compare_environments() {
+        let staging = Environment {
+            environment: "STAGING".to_string(),
+            environment_type: None,
+            config_data: json!({
+                "Region": {"Key": "STAGING"},
+                "DB": {"Endpoint": "db-staging.example.com", "Pool": 5},
+                "Hosts": ["web-staging-1"],
+                "Debug": true
+            }),
+        };
+        let prod = Environment {
+            environment: "PROD".to_string(),
+            environment_type: None,
+            config_data: json!({
+                "Region": {"Key": "PROD"},
+                "DB": {"Endpoint": "db-prod.example.com", "Pool": 20},
+                "Hosts": ["web-prod-1"]
+            }),
+        };
+
+        let comparison = compare_environments(&staging, &prod, false);
+        assert_eq!(comparison.changes.len(), 5);
+        assert_eq!(comparison.collapsed, 0);
+
+        let collapsed = compare_environments(&staging, &prod, true);
+        assert_eq!(
+            collapsed.changes,
+            vec![
+                Change::Changed {
+                    path: "DB.Pool".to_string(),
+                    from: json!(5),
+                    to: json!(20)
+                },
+                Change::Removed {
+                    path: "Debug".to_string(),
+                    value: json!(true)
+                },
+            ]
+        );
+        assert_eq!(collapsed.collapsed, 3);
+    }
+
+    #[test]
+    fn test_lookup() {
+        let config = json!({"Memcache": {"Servers": [{"Port": "1122"}]}});
+        assert_eq!(
+            lookup(&config, "Memcache.Servers.0.Port"),
+            Some(&json!("1122"))
+        );
+        assert_eq!(lookup(&config, "Memcache.Servers.1.Port"), None);
+        assert_eq!(lookup(&config, "Memcache.Missing"), None);
+        assert_eq!(lookup(&config, ""), Some(&config));
+    }
+}
@@ -18,6 +18,10 @@ pub enum HoganError {
     InternalTimeout,
     #[error("An error occurred parsing configuration {param}: {msg}")]
     InvalidConfiguration { param: String, msg: String },
+    #[error("Rendering failed. {msg}")]
+    RenderFailed { msg: String },
+    #[error("Check failed. {msg}")]
+    CheckFailed { msg: String },
     #[error("An unknown error occurred. {msg}")]
     UnknownError { msg: String },
 }
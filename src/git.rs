@@ -1,8 +1,12 @@
 use crate::error::HoganError;
 use anyhow::{Context, Result};
 use git2::build::RepoBuilder;
-use git2::{AutotagOption, Cred, FetchOptions, Reference, RemoteCallbacks, Repository, ResetType};
-use std::path::Path;
+use git2::{
+    AutotagOption, Commit, Cred, ErrorCode, FetchOptions, ObjectType, Oid, Reference,
+    RemoteCallbacks, Repository, ResetType, Status, StatusOptions, Tree, TreeWalkMode,
+    TreeWalkResult,
+};
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::str;
 use url::Url;
@@ -10,7 +14,7 @@ use url::Url;
 pub fn ext_clone(url: &Url, path: &Path) -> Result<()> {
     info!("Cloning {:?} to {:?}", url, path);
     let mut clone = Command::new("git")
-        .args(&["clone", &url.to_string(), path.to_str().unwrap()])
+        .args(["clone", url.as_ref(), path.to_str().unwrap()])
         .spawn()?;
     let result = clone.wait()?;
     info!("Clone output {}", result);
@@ -89,7 +93,7 @@ pub fn clone(
         ))
 }
 
-fn make_ssh_auth(ssh_key_path: &Path) -> RemoteCallbacks {
+fn make_ssh_auth(ssh_key_path: &Path) -> RemoteCallbacks<'_> {
     let mut callback = RemoteCallbacks::new();
     callback.credentials(move |_url, username_from_url, _allowed_types| {
         Cred::ssh_key(username_from_url.unwrap(), None, ssh_key_path, None)
@@ -98,7 +102,7 @@ fn make_ssh_auth(ssh_key_path: &Path) -> RemoteCallbacks {
     callback
 }
 
-fn make_password_auth(url: &Url) -> RemoteCallbacks {
+fn make_password_auth(url: &Url) -> RemoteCallbacks<'_> {
     if let Some(password) = url.password() {
         let mut callback = RemoteCallbacks::new();
         callback.credentials(move |_url, username_from_url, _allowed_type| {
@@ -127,7 +131,7 @@ pub fn ext_fetch(path: &Path, remote: &str) -> Result<()> {
     info!("Fetching {}", remote);
     let mut fetch_cmd = Command::new("git")
         .current_dir(path.to_str().unwrap())
-        .args(&["fetch", remote])
+        .args(["fetch", remote])
         .spawn()?;
 
     fetch_cmd.wait()?;
@@ -138,7 +142,7 @@ pub fn ext_maintenance(path: &Path) -> Result<()> {
     info!("Performing maintenance");
     let mut maintenance_cmd = Command::new("git")
         .current_dir(path.to_str().unwrap())
-        .args(&["maintenance", "run", "--auto"])
+        .args(["maintenance", "run", "--auto"])
         .spawn()?;
     maintenance_cmd.wait()?;
     Ok(())
@@ -262,6 +266,21 @@ pub fn build_repo(path: &str) -> Result<Repository> {
     Repository::discover(path).map_err(|e| e.into())
 }
 
+/// Opens the repository rooted at the path, without searching its parent directories
+pub fn open_repo(path: &Path) -> Result<Repository> {
+    Repository::open(path).map_err(|e| e.into())
+}
+
+/// Whether the working directory has changes which aren't committed, including untracked files
+pub fn has_uncommitted_changes(repo: &Repository) -> Result<bool> {
+    let mut options = StatusOptions::new();
+    options.include_untracked(true).include_ignored(false);
+    let statuses = repo
+        .statuses(Some(&mut options))
+        .map_err::<HoganError, _>(|e| e.into())?;
+    Ok(statuses.iter().any(|s| s.status() != Status::CURRENT))
+}
+
 fn find_ref_sha(reference: &Reference) -> Result<String> {
     if let Some(target) = reference.target() {
         let sha = target.to_string();
@@ -291,3 +310,97 @@ pub fn find_branch_head(repo: &Repository, branch: &str) -> Result<String> {
         .context(format!("Unable to find branch {}", branch))?;
     find_ref_sha(&branch_ref).context(format!("Unable to find the head SHA of branch {}", branch))
 }
+
+pub fn find_commit<'r>(repo: &'r Repository, revision: &str) -> Result<Commit<'r>> {
+    repo.revparse_single(revision)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|_| HoganError::UnknownSHA {
+            sha: revision.to_owned(),
+        })
+        .context(format!("Unable to find commit {}", revision))
+}
+
+pub fn repo_relative_path(repo: &Repository, path: &Path) -> Result<PathBuf> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| HoganError::GitError {
+            msg: "No working directory found for git repository".to_string(),
+        })?
+        .canonicalize()?;
+
+    path.canonicalize()?
+        .strip_prefix(&workdir)
+        .map(|p| p.to_path_buf())
+        .map_err(|_| {
+            HoganError::GitError {
+                msg: format!("{:?} is not within the git repository", path),
+            }
+            .into()
+        })
+}
+
+pub fn find_subtree<'r>(
+    repo: &'r Repository,
+    commit: &Commit<'r>,
+    path: &Path,
+) -> Result<Option<Tree<'r>>> {
+    let tree = commit
+        .tree()
+        .map_err::<HoganError, _>(|e| e.into())
+        .context(format!("Unable to read the tree of commit {}", commit.id()))?;
+
+    if path.as_os_str().is_empty() {
+        return Ok(Some(tree));
+    }
+
+    match tree.get_path(path) {
+        Ok(entry) => entry
+            .to_object(repo)
+            .and_then(|object| object.peel_to_tree())
+            .map(Some)
+            .map_err::<HoganError, _>(|e| e.into())
+            .context(format!(
+                "Unable to read {:?} in commit {}",
+                path,
+                commit.id()
+            )),
+        Err(e) if e.code() == ErrorCode::NotFound => Ok(None),
+        Err(e) => Err(HoganError::from(e).into()),
+    }
+}
+
+pub fn read_tree_files(repo: &Repository, tree: &Tree) -> Result<Vec<(PathBuf, Vec<u8>)>> {
+    let mut files = Vec::new();
+    tree.walk(TreeWalkMode::PreOrder, |root, entry| {
+        if entry.kind() == Some(ObjectType::Blob) {
+            if let (Some(name), Ok(blob)) = (entry.name(), repo.find_blob(entry.id())) {
+                files.push((Path::new(root).join(name), blob.content().to_vec()));
+            }
+        }
+        TreeWalkResult::Ok
+    })
+    .map_err::<HoganError, _>(|e| e.into())
+    .context("Error walking git tree")?;
+
+    Ok(files)
+}
+
+/// Lists the commits reachable from the given commit by following first parents, newest first
+pub fn first_parent_history(repo: &Repository, commit: &Commit) -> Result<Vec<Oid>> {
+    let mut revwalk = repo
+        .revwalk()
+        .map_err::<HoganError, _>(|e| e.into())
+        .context("Unable to walk repository history")?;
+    revwalk
+        .push(commit.id())
+        .and_then(|_| revwalk.simplify_first_parent())
+        .map_err::<HoganError, _>(|e| e.into())
+        .context(format!("Unable to walk history from {}", commit.id()))?;
+
+    revwalk
+        .map(|oid| {
+            oid.map_err::<HoganError, _>(|e| e.into())
+                .context("Error walking repository history")
+        })
+        .collect()
+}
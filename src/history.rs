@@ -0,0 +1,314 @@
+use crate::config::{environments_from_files, ConfigDir};
+use crate::diff::lookup;
+use crate::error::HoganError;
+use crate::git;
+use anyhow::{Context, Result};
+use git2::{Oid, Time};
+use regex::Regex;
+use serde_json::Value;
+use std::fmt;
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct ValueChange {
+    pub sha: String,
+    pub author: String,
+    pub date: String,
+    pub message: String,
+    pub old_value: Option<Value>,
+    pub new_value: Option<Value>,
+}
+
+impl fmt::Display for ValueChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn format_value(value: &Option<Value>) -> String {
+            value
+                .as_ref()
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| "(unset)".to_string())
+        }
+
+        writeln!(f, "{} {} {}", &self.sha[..7], self.date, self.author)?;
+        for line in self.message.trim().lines() {
+            writeln!(f, "    {}", line)?;
+        }
+        writeln!(
+            f,
+            "    {} -> {}",
+            format_value(&self.old_value),
+            format_value(&self.new_value)
+        )
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct ValueHistory {
+    pub environment: String,
+    pub path: String,
+    pub changes: Vec<ValueChange>,
+}
+
+impl fmt::Display for ValueHistory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.changes.is_empty() {
+            return writeln!(f, "No changes to {} for {}", self.path, self.environment);
+        }
+
+        for change in &self.changes {
+            writeln!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+/// Walks the first parent history from `revision` and lists the commits where the merged value at
+/// `path` changed for `env`. Every commit is merged from its global, type and environment layers,
+/// so changes made through an environment type or global config are found as well. It is an error
+/// for `env` not to exist at `revision`
+pub fn value_history(
+    config_dir: &ConfigDir,
+    revision: &str,
+    env: &str,
+    path: &str,
+    filter: &Regex,
+    limit: Option<usize>,
+) -> Result<ValueHistory> {
+    let git_repo = git::build_repo(config_dir.directory().to_str().unwrap())
+        .with_context(|| format!("Finding history of {} for {}", path, env))?;
+    let start = git::find_commit(&git_repo, revision)?;
+    let internal_path = git::repo_relative_path(&git_repo, config_dir.directory())?;
+
+    // Environments are matched case-insensitively like the environment filter, and the name
+    // reported is the one in the configs at the starting revision
+    let start_files = match git::find_subtree(&git_repo, &start, &internal_path)? {
+        Some(tree) => git::read_tree_files(&git_repo, &tree)?,
+        None => Vec::new(),
+    };
+    let env = environments_from_files(&start_files, filter)
+        .into_iter()
+        .find(|e| e.environment.eq_ignore_ascii_case(env))
+        .map(|e| e.environment)
+        .ok_or_else(|| HoganError::UnknownEnvironment {
+            sha: revision.to_owned(),
+            env: env.to_owned(),
+        })?;
+
+    // The merged value for a commit, keyed by the id of its config tree. Unchanged trees reuse
+    // the previously merged value rather than re-reading every config file
+    let value_at =
+        |oid: &Oid, newer: Option<&(Oid, Option<Value>)>| -> Result<(Oid, Option<Value>)> {
+            let commit = git_repo
+                .find_commit(*oid)
+                .map_err::<HoganError, _>(|e| e.into())?;
+            let tree = match git::find_subtree(&git_repo, &commit, &internal_path)? {
+                Some(tree) => tree,
+                None => return Ok((Oid::zero(), None)),
+            };
+
+            match newer {
+                Some((tree_id, value)) if *tree_id == tree.id() => Ok((tree.id(), value.clone())),
+                _ => {
+                    let files = git::read_tree_files(&git_repo, &tree)?;
+                    let value = environments_from_files(&files, filter)
+                        .into_iter()
+                        .find(|e| e.environment.eq_ignore_ascii_case(&env))
+                        .and_then(|e| lookup(&e.config_data, path).cloned());
+                    Ok((tree.id(), value))
+                }
+            }
+        };
+
+    let history = git::first_parent_history(&git_repo, &start)?;
+    let mut changes = Vec::new();
+    let mut current = match history.first() {
+        Some(oid) => value_at(oid, None)?,
+        None => (Oid::zero(), None),
+    };
+
+    for (i, oid) in history.iter().enumerate() {
+        let parent = match history.get(i + 1) {
+            Some(parent_oid) => Some(value_at(parent_oid, Some(&current))?),
+            None => None,
+        };
+        let old_value = parent.as_ref().and_then(|(_, value)| value.clone());
+
+        if old_value != current.1 {
+            let commit = git_repo
+                .find_commit(*oid)
+                .map_err::<HoganError, _>(|e| e.into())?;
+            let author = commit.author();
+            changes.push(ValueChange {
+                sha: oid.to_string(),
+                author: format!(
+                    "{} <{}>",
+                    author.name().unwrap_or(""),
+                    author.email().unwrap_or("")
+                ),
+                date: format_time(&author.when()),
+                message: commit.message().unwrap_or("").to_owned(),
+                old_value,
+                new_value: current.1.clone(),
+            });
+
+            if limit.is_some_and(|limit| changes.len() >= limit) {
+                break;
+            }
+        }
+
+        match parent {
+            Some(parent) => current = parent,
+            None => break,
+        }
+    }
+
+    Ok(ValueHistory {
+        environment: env,
+        path: path.to_owned(),
+        changes,
+    })
+}
+
+// Formats a git timestamp in its original offset, e.g. 2021-06-15 14:03:11 +0200
+fn format_time(time: &Time) -> String {
+    let offset = time.offset_minutes() as i64;
+    let local = time.seconds() + offset * 60;
+    let days = local.div_euclid(86_400);
+    let seconds = local.rem_euclid(86_400);
+
+    // Converts days since the unix epoch to a civil date. See http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} {}{:02}{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60,
+        if offset < 0 { '-' } else { '+' },
+        offset.abs() / 60,
+        offset.abs() % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::build_regex;
+    use crate::config::tests::{commit_all, init_config_repo};
+    use crate::config::ConfigUrl;
+    use serde_json::json;
+    use std::fs;
+    use std::path::Path;
+
+    fn update_file(path: &Path, from: &str, to: &str) {
+        let updated = fs::read_to_string(path).unwrap().replace(from, to);
+        fs::write(path, updated).unwrap();
+    }
+
+    #[test]
+    fn test_value_history() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo = init_config_repo(temp_dir.path());
+        let configs = temp_dir.path().join("configs");
+
+        update_file(
+            &configs.join("config.TEST.json"),
+            r#""Key": "TEST""#,
+            r#""Key": "UPDATED""#,
+        );
+        let update_sha = commit_all(&repo, "Update TEST region");
+
+        update_file(
+            &configs.join("config.TEST2.json"),
+            r#""Key": "TEST2""#,
+            r#""Key": "OTHER""#,
+        );
+        commit_all(&repo, "Update TEST2 region");
+
+        update_file(
+            &configs.join("envTypes/alpha.json"),
+            r#""EnvironmentType": "alpha"
+  }"#,
+            r#""EnvironmentType": "beta"
+  }"#,
+        );
+        let type_sha = commit_all(&repo, "Update alpha type");
+
+        let config_dir = ConfigDir::new(
+            ConfigUrl::File { path: configs },
+            Path::new(""),
+            false,
+            false,
+            false,
+        )
+        .unwrap();
+        let filter = build_regex("config\\..+\\.json$").unwrap();
+
+        let changes = value_history(&config_dir, "HEAD", "TEST", "Region.Key", &filter, None)
+            .unwrap()
+            .changes;
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].sha, update_sha);
+        assert_eq!(changes[0].message, "Update TEST region");
+        assert_eq!(changes[0].author, "Hogan <hogan@example.com>");
+        assert_eq!(changes[0].old_value, Some(json!("TEST")));
+        assert_eq!(changes[0].new_value, Some(json!("UPDATED")));
+        assert_eq!(changes[1].old_value, None);
+        assert_eq!(changes[1].new_value, Some(json!("TEST")));
+
+        let limited =
+            value_history(&config_dir, "HEAD", "TEST", "Region.Key", &filter, Some(1)).unwrap();
+        assert_eq!(limited.changes.len(), 1);
+
+        let lowercase =
+            value_history(&config_dir, "HEAD", "test", "Region.Key", &filter, None).unwrap();
+        assert_eq!(lowercase.environment, "TEST");
+        assert_eq!(lowercase.changes.len(), 2);
+
+        let unknown =
+            value_history(&config_dir, "HEAD", "MISSING", "Region.Key", &filter, None).unwrap_err();
+        assert!(matches!(
+            unknown.downcast_ref::<HoganError>(),
+            Some(HoganError::UnknownEnvironment { env, .. }) if env == "MISSING"
+        ));
+
+        let type_changes = value_history(
+            &config_dir,
+            "HEAD",
+            "ENVTYPE",
+            "EnvironmentType",
+            &filter,
+            None,
+        )
+        .unwrap()
+        .changes;
+        assert_eq!(type_changes.len(), 2);
+        assert_eq!(type_changes[0].sha, type_sha);
+        assert_eq!(type_changes[0].new_value, Some(json!("beta")));
+    }
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(&Time::new(0, 0)), "1970-01-01 00:00:00 +0000");
+        assert_eq!(
+            format_time(&Time::new(1_623_758_591, 120)),
+            "2021-06-15 14:03:11 +0200"
+        );
+        assert_eq!(
+            format_time(&Time::new(1_623_758_591, -300)),
+            "2021-06-15 07:03:11 -0500"
+        );
+    }
+}
@@ -1,13 +1,23 @@
 #[macro_use]
+extern crate lazy_static;
+#[macro_use]
 extern crate log;
 #[macro_use]
 extern crate serde_derive;
 
 pub mod config;
+pub mod coverage;
+pub mod diff;
 pub mod error;
 pub mod git;
+pub mod history;
+pub mod lint;
+pub mod manifest;
 pub mod template;
+#[cfg(test)]
+mod test_repo;
 pub mod transform;
+pub mod vars;
 
 use regex::Regex;
 use std::path::{Path, PathBuf};
@@ -18,12 +28,12 @@ pub fn find_file_paths(path: &Path, filter: Regex) -> Box<dyn Iterator<Item = Pa
         entry
             .file_name()
             .to_str()
-            .map(|s| filter.is_match(&s))
+            .map(|s| filter.is_match(s))
             .unwrap_or(false)
     }
 
-    println!("Finding Files: {:?}", path);
-    println!("regex: /{}/", filter);
+    debug!("Finding Files: {:?}", path);
+    debug!("regex: /{}/", filter);
 
     Box::new(
         WalkDir::new(path)
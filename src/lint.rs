@@ -0,0 +1,291 @@
+use crate::template::Template;
+use crate::transform::helper_arity;
+use handlebars::template::{
+    DecoratorTemplate, HelperTemplate, Parameter, Template as Ast, TemplateElement, TemplateMapping,
+};
+use handlebars::Handlebars;
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// A problem found in a template without rendering it
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct LintIssue {
+    /// Position in the template file counting from 1, if the parser reported one
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, "{}:{}: {}", line, column, self.message),
+            _ => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Compiles the template without rendering it, reporting syntax errors such as unbalanced
+/// blocks, helpers which aren't registered, helpers given the wrong number of parameters and
+/// partials which aren't registered
+pub fn lint(handlebars: &Handlebars, template: &Template) -> Vec<LintIssue> {
+    let line_offset = template.front_matter.lines;
+
+    let ast = match Ast::compile2(&template.contents, true) {
+        Ok(ast) => ast,
+        Err(e) => {
+            return vec![LintIssue {
+                line: e.line_no.map(|line| line + line_offset),
+                column: e.column_no,
+                message: e.reason.to_string(),
+            }]
+        }
+    };
+
+    let mut linter = Linter {
+        handlebars,
+        inline_partials: BTreeSet::new(),
+        issues: Vec::new(),
+    };
+    linter.find_inline_partials(&ast);
+    linter.template(&ast);
+
+    for issue in linter.issues.iter_mut() {
+        issue.line = issue.line.map(|line| line + line_offset);
+    }
+    linter.issues
+}
+
+struct Linter<'a, 'reg> {
+    handlebars: &'a Handlebars<'reg>,
+    /// Partials defined in the template with {{#*inline}}
+    inline_partials: BTreeSet<String>,
+    issues: Vec<LintIssue>,
+}
+
+impl<'a, 'reg> Linter<'a, 'reg> {
+    fn find_inline_partials(&mut self, ast: &Ast) {
+        for element in &ast.elements {
+            match element {
+                TemplateElement::DecoratorBlock(decorator) => {
+                    if decorator.name.as_name() == Some("inline") {
+                        if let Some(Parameter::Literal(name)) = decorator.params.first() {
+                            if let Some(name) = name.as_str() {
+                                self.inline_partials.insert(name.to_owned());
+                            }
+                        }
+                    }
+                    if let Some(ref template) = decorator.template {
+                        self.find_inline_partials(template);
+                    }
+                }
+                TemplateElement::HelperBlock(helper) => {
+                    for template in helper.template.iter().chain(helper.inverse.iter()) {
+                        self.find_inline_partials(template);
+                    }
+                }
+                TemplateElement::PartialBlock(partial) => {
+                    if let Some(ref template) = partial.template {
+                        self.find_inline_partials(template);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn template(&mut self, ast: &Ast) {
+        for (index, element) in ast.elements.iter().enumerate() {
+            let position = ast
+                .mapping
+                .as_ref()
+                .and_then(|m| m.get(index))
+                .map(|TemplateMapping(line, column)| (*line, *column));
+            self.element(element, position);
+        }
+    }
+
+    fn element(&mut self, element: &TemplateElement, position: Option<(usize, usize)>) {
+        match element {
+            TemplateElement::Expression(helper) => {
+                let is_helper = !helper.params.is_empty()
+                    || !helper.hash.is_empty()
+                    || helper
+                        .name
+                        .as_name()
+                        .is_some_and(|n| self.handlebars.get_helper(n).is_some());
+                if is_helper {
+                    self.helper(helper, position);
+                }
+            }
+            TemplateElement::HelperBlock(helper) => {
+                self.helper(helper, position);
+                for template in helper.template.iter().chain(helper.inverse.iter()) {
+                    self.template(template);
+                }
+            }
+            TemplateElement::PartialExpression(partial)
+            | TemplateElement::PartialBlock(partial) => {
+                self.partial(partial, position);
+            }
+            TemplateElement::DecoratorBlock(decorator) => {
+                if let Some(ref template) = decorator.template {
+                    self.template(template);
+                }
+            }
+            TemplateElement::HTMLExpression(_)
+            | TemplateElement::RawString(_)
+            | TemplateElement::Comment(_)
+            | TemplateElement::DecoratorExpression(_) => {}
+        }
+    }
+
+    fn helper(&mut self, helper: &HelperTemplate, position: Option<(usize, usize)>) {
+        for param in helper.params.iter().chain(helper.hash.values()) {
+            self.parameter(param, position);
+        }
+
+        let name = match helper.name.as_name() {
+            Some(name) => name,
+            None => return,
+        };
+        if self.handlebars.get_helper(name).is_none() {
+            self.add(position, format!("Unknown helper {}", name));
+            return;
+        }
+
+        let count = helper.params.len();
+        match helper_arity(name) {
+            Some((min, _)) if count < min => self.add(
+                position,
+                format!(
+                    "Helper {} requires at least {} parameter(s), found {}",
+                    name, min, count
+                ),
+            ),
+            Some((_, Some(max))) if count > max => self.add(
+                position,
+                format!(
+                    "Helper {} accepts at most {} parameter(s), found {}",
+                    name, max, count
+                ),
+            ),
+            _ => {}
+        }
+    }
+
+    fn parameter(&mut self, param: &Parameter, position: Option<(usize, usize)>) {
+        if let Parameter::Subexpression(subexpression) = param {
+            if let TemplateElement::Expression(ref helper) = *subexpression.element {
+                self.helper(helper, position);
+            }
+        }
+    }
+
+    fn partial(&mut self, partial: &DecoratorTemplate, position: Option<(usize, usize)>) {
+        for param in partial.params.iter().chain(partial.hash.values()) {
+            self.parameter(param, position);
+        }
+
+        // Dynamic partial names, e.g. {{> (lookup . "name")}}, can only be checked when rendering
+        if let Parameter::Name(_) | Parameter::Path(_) = partial.name {
+            let name = partial.name.as_name().unwrap_or_default();
+            if self.handlebars.get_template(name).is_none() && !self.inline_partials.contains(name)
+            {
+                self.add(position, format!("Unknown partial {}", name));
+            }
+        }
+
+        if let Some(ref template) = partial.template {
+            self.template(template);
+        }
+    }
+
+    fn add(&mut self, position: Option<(usize, usize)>, message: String) {
+        self.issues.push(LintIssue {
+            line: position.map(|(line, _)| line),
+            column: position.map(|(_, column)| column),
+            message,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+
+    fn lint_source(source: &str) -> Vec<String> {
+        let mut handlebars = crate::transform::handlebars(false);
+        handlebars.register_partial("logging", "{{Level}}").unwrap();
+        let template = Template::new(
+            Path::new("templates"),
+            PathBuf::from("templates/Web.template.config"),
+            source,
+        )
+        .unwrap();
+
+        lint(&handlebars, &template)
+            .iter()
+            .map(|issue| issue.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_template() {
+        assert_eq!(
+            Vec::<String>::new(),
+            lint_source(
+                "{{#if ConfigEnabled}}\n\
+             {{#comma-list Memcache.Servers}}{{Endpoint}}:{{Port}}{{/comma-list}}\n\
+             {{or DB.Endpoint (lowercase Region.Key) \"default\"}}\n\
+             {{#*inline \"local\"}}{{Key}}{{/inline}}{{> local}}{{> logging}}\n\
+             {{/if}}"
+            )
+        );
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        assert_eq!(
+            lint_source("---hogan\nmode: 0600\n---\n{{#if Enabled}}\n{{Key}}\n{{/each}}"),
+            vec!["6:1: helper \"if\" was opened, but \"each\" is closing"]
+        );
+        assert_eq!(
+            lint_source("{{#if Enabled}}"),
+            vec!["1:16: invalid handlebars syntax."]
+        );
+        assert_eq!(
+            lint_source("{{Key"),
+            vec!["1:6: invalid handlebars syntax."]
+        );
+    }
+
+    #[test]
+    fn test_unknown_helpers_and_partials() {
+        assert_eq!(
+            lint_source(
+                "{{shout Key}}\n{{#if (strip Key)}}{{/if}}\n{{#each Items}}{{> missing}}{{/each}}"
+            ),
+            vec![
+                "1:1: Unknown helper shout",
+                "2:1: Unknown helper strip",
+                "3:16: Unknown partial missing",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_helper_arity() {
+        assert_eq!(
+            lint_source("{{or Key}}\n{{#if A B}}{{/if}}\n{{lowercase}}\n{{eq (lowercase) A}}"),
+            vec![
+                "1:1: Helper or requires at least 2 parameter(s), found 1",
+                "2:1: Helper if accepts at most 1 parameter(s), found 2",
+                "3:1: Helper lowercase requires at least 1 parameter(s), found 0",
+                "4:1: Helper lowercase requires at least 1 parameter(s), found 0",
+            ]
+        );
+    }
+}
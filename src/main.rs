@@ -10,6 +10,8 @@ use anyhow::{Context, Result};
 use structopt::StructOpt;
 
 mod app;
+#[cfg(test)]
+mod test_repo;
 
 fn main() -> Result<()> {
     let opt = App::from_args();
@@ -27,16 +29,89 @@ fn main() -> Result<()> {
             environments_regex,
             templates_regex,
             common,
-            ignore_existing,
+            options,
         } => {
             cli::cli(
                 templates_path,
                 environments_regex,
                 templates_regex,
                 common,
-                ignore_existing,
+                options,
             )?;
         }
+        AppCommand::Diff {
+            common,
+            from,
+            to,
+            environments_regex,
+            format,
+        } => {
+            cli::diff(common, from, to, environments_regex, format)?;
+        }
+        AppCommand::Compare {
+            common,
+            from,
+            to,
+            sha,
+            collapse_env_names,
+            format,
+        } => {
+            cli::compare(common, from, to, sha, collapse_env_names, format)?;
+        }
+        AppCommand::Log {
+            common,
+            environment,
+            path,
+            from,
+            limit,
+            format,
+        } => {
+            cli::log(common, environment, path, from, limit, format)?;
+        }
+        AppCommand::Vars {
+            common,
+            template_path,
+            templates_path,
+            partials_path,
+            environments_regex,
+            format,
+        } => {
+            cli::vars(
+                common,
+                template_path,
+                templates_path,
+                partials_path,
+                environments_regex,
+                format,
+            )?;
+        }
+        AppCommand::Coverage {
+            common,
+            templates_path,
+            templates_regex,
+            partials_path,
+            environments_regex,
+            unused,
+            format,
+        } => {
+            cli::coverage(
+                common,
+                templates_path,
+                templates_regex,
+                partials_path,
+                environments_regex,
+                unused,
+                format,
+            )?;
+        }
+        AppCommand::Validate {
+            templates_path,
+            templates_regex,
+            partials_path,
+            format,
+        } => {
+            cli::validate(templates_path, templates_regex, partials_path, format)?;
+        }
         AppCommand::Server {
             common,
             port,
@@ -0,0 +1,166 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use tempfile::NamedTempFile;
+
+/// Name of the manifest written to the output directory by hogan transform
+pub const MANIFEST_FILE: &str = ".hogan-manifest.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct ManifestEntry {
+    /// Output path relative to the directory holding the manifest
+    pub path: PathBuf,
+    /// Template path relative to the templates directory
+    pub template: PathBuf,
+    pub environment: String,
+    /// Head SHA of the configs repo when the file was generated, if the configs are in git
+    pub config_sha: Option<String>,
+    /// Hex encoded SHA-256 of the generated contents
+    pub hash: String,
+}
+
+/// Lists every file generated by a transform, so outputs which are no longer generated can be
+/// found and removed later
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct Manifest {
+    pub entries: Vec<ManifestEntry>,
+}
+
+pub fn content_hash(contents: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(contents))
+}
+
+impl Manifest {
+    /// Loads a manifest, returning an empty one if it doesn't exist yet
+    pub fn load(path: &Path) -> Result<Manifest> {
+        match fs::read(path) {
+            Ok(contents) => serde_json::from_slice(&contents)
+                .with_context(|| format!("Error parsing manifest {:?}", path)),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(Manifest::default()),
+            Err(e) => Err(e).with_context(|| format!("Error reading manifest {:?}", path)),
+        }
+    }
+
+    /// Writes through a temp file next to the manifest which is renamed into place, so a failure
+    /// never leaves a truncated manifest for the next run to work from
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut contents = serde_json::to_vec_pretty(self)?;
+        contents.push(b'\n');
+
+        let parent = path
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."));
+        let mut temp_file = NamedTempFile::new_in(parent)
+            .with_context(|| format!("Error creating a temp file in {:?}", parent))?;
+        temp_file
+            .write_all(&contents)
+            .with_context(|| format!("Error writing manifest {:?}", path))?;
+        temp_file
+            .persist(path)
+            .with_context(|| format!("Error writing manifest {:?}", path))?;
+        Ok(())
+    }
+
+    /// Combines the entries generated by this run with the previous manifest. Previous entries
+    /// which were in scope of this run but not generated again are returned as stale, while
+    /// entries out of scope, e.g. for filtered out environments, are carried over
+    pub fn update(
+        previous: Manifest,
+        generated: Vec<ManifestEntry>,
+        in_scope: impl Fn(&ManifestEntry) -> bool,
+    ) -> (Manifest, Vec<ManifestEntry>) {
+        let generated_paths = generated
+            .iter()
+            .map(|e| e.path.clone())
+            .collect::<BTreeSet<PathBuf>>();
+
+        let (stale, kept): (Vec<ManifestEntry>, Vec<ManifestEntry>) = previous
+            .entries
+            .into_iter()
+            .filter(|e| !generated_paths.contains(&e.path))
+            .partition(|e| in_scope(e));
+
+        let mut entries = generated;
+        entries.extend(kept);
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+        (Manifest { entries }, stale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, environment: &str) -> ManifestEntry {
+        ManifestEntry {
+            path: PathBuf::from(path),
+            template: PathBuf::from("Web.template.config"),
+            environment: environment.to_string(),
+            config_sha: Some("abc1234".to_string()),
+            hash: content_hash(environment.as_bytes()),
+        }
+    }
+
+    #[test]
+    fn test_content_hash() {
+        assert_eq!(
+            content_hash(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn test_update() {
+        let previous = Manifest {
+            entries: vec![
+                entry("Web.TEST.config", "TEST"),
+                entry("Web.OLD.config", "OLD"),
+                entry("Web.PROD.config", "PROD"),
+            ],
+        };
+        let generated = vec![
+            entry("Web.TEST.config", "TEST"),
+            entry("Web.NEW.config", "NEW"),
+        ];
+
+        let (manifest, stale) = Manifest::update(previous, generated, |e| e.environment != "PROD");
+
+        assert_eq!(
+            manifest
+                .entries
+                .iter()
+                .map(|e| e.path.to_str().unwrap())
+                .collect::<Vec<_>>(),
+            vec!["Web.NEW.config", "Web.PROD.config", "Web.TEST.config"]
+        );
+        assert_eq!(stale, vec![entry("Web.OLD.config", "OLD")]);
+    }
+
+    #[test]
+    fn test_load_and_save() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join(MANIFEST_FILE);
+        assert_eq!(Manifest::load(&path).unwrap(), Manifest::default());
+
+        let manifest = Manifest {
+            entries: vec![entry("Web.TEST.config", "TEST")],
+        };
+        manifest.save(&path).unwrap();
+        assert_eq!(Manifest::load(&path).unwrap(), manifest);
+        assert!(fs::read_to_string(&path)
+            .unwrap()
+            .contains("\"ConfigSha\": \"abc1234\""));
+
+        // Saving again replaces the manifest without leaving temp files behind
+        Manifest::default().save(&path).unwrap();
+        assert_eq!(Manifest::load(&path).unwrap(), Manifest::default());
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+}
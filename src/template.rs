@@ -1,16 +1,21 @@
-use crate::config::Environment;
+use crate::config::{build_regex, Environment};
 use crate::error::HoganError;
 use crate::find_file_paths;
+use crate::transform::escape::{with_escape_mode, EscapeMode};
 use anyhow::{Context, Result};
-use handlebars::Handlebars;
+use handlebars::{Handlebars, TemplateRenderError};
 use regex::Regex;
+use serde_json::{Map, Value};
+use similar::TextDiff;
 use zip::write::{FileOptions, ZipWriter};
 use zip::CompressionMethod::Stored;
 
 use std::clone::Clone;
+use std::fmt;
 use std::fs;
-use std::io::{Cursor, Write};
-use std::path::PathBuf;
+use std::io::{Cursor, ErrorKind, Write};
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
 
 pub struct TemplateDir {
     directory: PathBuf,
@@ -28,76 +33,693 @@ impl TemplateDir {
         }
     }
 
-    pub fn find(&self, filter: Regex) -> Vec<Template> {
-        find_file_paths(&self.directory, filter)
-            .filter_map(|path| Template::from_path_buf(path).ok())
-            .collect()
+    /// Finds templates matching the filter. Files which can not be read are skipped, and
+    /// templates with invalid front matter are returned separately so they don't stop the others
+    /// from loading
+    pub fn find(&self, filter: Regex) -> (Vec<Template>, Vec<InvalidTemplate>) {
+        let mut templates = Vec::new();
+        let mut invalid = Vec::new();
+        for path in find_file_paths(&self.directory, filter) {
+            let contents = match fs::read_to_string(&path) {
+                Ok(contents) => contents,
+                Err(_) => continue,
+            };
+            match Template::new(&self.directory, path.clone(), &contents) {
+                Ok(template) => templates.push(template),
+                Err(error) => invalid.push(InvalidTemplate { path, error }),
+            }
+        }
+        (templates, invalid)
+    }
+}
+
+/// A template which couldn't be loaded because of its front matter
+#[derive(Debug)]
+pub struct InvalidTemplate {
+    pub path: PathBuf,
+    pub error: anyhow::Error,
+}
+
+const PLACEHOLDERS: [&str; 5] = ["env", "envType", "stem", "ext", "dir"];
+
+fn placeholder_regex() -> Regex {
+    Regex::new(r"\{([^{}]*)\}").unwrap()
+}
+
+/// Describes where a rendered template is written, relative to the templates directory.
+/// Supports the placeholders {env}, {envType}, {stem}, {ext} and {dir}
+/// e.g. out/{env}/{dir}/{stem}.{ext}
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputPattern {
+    pattern: String,
+}
+
+impl FromStr for OutputPattern {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s.trim().is_empty() {
+            return Err(HoganError::InvalidConfiguration {
+                param: "output pattern".to_string(),
+                msg: "The output pattern can not be empty".to_string(),
+            }
+            .into());
+        }
+
+        // Outputs must stay inside the output directory, and the bundle, as pruning deletes them
+        if Path::new(s)
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(HoganError::InvalidConfiguration {
+                param: "output pattern".to_string(),
+                msg: format!(
+                    "The output pattern {} must be a relative path without .. components",
+                    s
+                ),
+            }
+            .into());
+        }
+
+        if let Some(unknown) = placeholder_regex()
+            .captures_iter(s)
+            .find(|c| !PLACEHOLDERS.contains(&&c[1]))
+        {
+            return Err(HoganError::InvalidConfiguration {
+                param: "output pattern".to_string(),
+                msg: format!(
+                    "Unknown placeholder {} in {}. Expected one of {{{}}}",
+                    &unknown[0],
+                    s,
+                    PLACEHOLDERS.join("}, {")
+                ),
+            }
+            .into());
+        }
+
+        Ok(OutputPattern {
+            pattern: s.to_owned(),
+        })
+    }
+}
+
+impl OutputPattern {
+    pub fn relative_output_path(&self, template: &Template, environment: &Environment) -> PathBuf {
+        let (stem, ext) = template.name_parts();
+        let dir = template.relative_dir();
+
+        let expanded =
+            placeholder_regex().replace_all(&self.pattern, |c: &regex::Captures| match &c[1] {
+                "env" => environment.environment.to_owned(),
+                "envType" => environment.environment_type.to_owned().unwrap_or_default(),
+                "stem" => stem.to_owned(),
+                "ext" => ext.to_owned(),
+                "dir" => dir.to_string_lossy().into_owned(),
+                _ => c[0].to_owned(),
+            });
+
+        Path::new(expanded.as_ref()).components().collect()
+    }
+}
+
+/// Optional header at the start of a template, between ---hogan and --- lines, declaring which
+/// environments the template applies to and how its output is written e.g.
+/// ---hogan
+/// environments: PROD.*
+/// environmentTypes: web, api
+/// output: {dir}/{env}/{stem}.{ext}
+/// mode: 0600
+/// ---
+#[derive(Debug, Clone, Default)]
+pub struct FrontMatter {
+    /// Must match the whole environment name, case insensitive
+    pub environments: Option<Regex>,
+    /// Environment types the template applies to, case insensitive. Empty allows all types
+    pub environment_types: Vec<String>,
+    pub output_pattern: Option<OutputPattern>,
+    /// Unix permission bits of the rendered file
+    pub mode: Option<u32>,
+    /// Number of source lines taken up by the front matter, including the delimiters
+    pub lines: usize,
+}
+
+/// Opens the front matter. A plain --- would clash with the YAML document start marker
+const FRONT_MATTER_START: &str = "---hogan";
+const FRONT_MATTER_END: &str = "---";
+
+impl FrontMatter {
+    /// Splits the front matter off the template source, returning it and the remaining body
+    pub fn parse(source: &str) -> Result<(Option<FrontMatter>, &str)> {
+        let mut lines = source.split_inclusive('\n');
+        if lines.next().map(|l| l.trim_end()) != Some(FRONT_MATTER_START) {
+            return Ok((None, source));
+        }
+
+        let mut front_matter = FrontMatter::default();
+        let mut offset = source.find('\n').map(|i| i + 1).unwrap_or(source.len());
+        for (index, line) in lines.enumerate() {
+            offset += line.len();
+            let line = line.trim();
+
+            if line == FRONT_MATTER_END {
+                front_matter.lines = index + 2;
+                return Ok((Some(front_matter), &source[offset..]));
+            }
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once(':')
+                .map(|(k, v)| (k.trim(), v.trim()))
+                .ok_or_else(|| {
+                    front_matter_error(format!("Expected key: value, found {}", line))
+                })?;
+
+            match key {
+                "environments" => {
+                    front_matter.environments = Some(
+                        build_regex(&format!("^(?:{})$", value))
+                            .map_err(|e| front_matter_error(format!("{:#}", e)))?,
+                    )
+                }
+                "environmentTypes" => {
+                    front_matter.environment_types = value
+                        .split(',')
+                        .map(|t| t.trim().to_owned())
+                        .filter(|t| !t.is_empty())
+                        .collect()
+                }
+                "output" => {
+                    front_matter.output_pattern = Some(
+                        value
+                            .parse()
+                            .map_err(|e| front_matter_error(format!("{:#}", e)))?,
+                    )
+                }
+                "mode" => {
+                    front_matter.mode = Some(u32::from_str_radix(value, 8).map_err(|_| {
+                        front_matter_error(format!("Invalid octal file mode {}", value))
+                    })?)
+                }
+                _ => {
+                    return Err(front_matter_error(format!(
+                        "Unknown key {}. Expected one of environments, environmentTypes, output or mode",
+                        key
+                    ))
+                    .into())
+                }
+            }
+        }
+
+        Err(front_matter_error("Missing closing ---".to_string()).into())
+    }
+
+    pub fn applies_to(&self, environment: &Environment) -> bool {
+        let name_matches = self
+            .environments
+            .as_ref()
+            .map(|r| r.is_match(&environment.environment))
+            .unwrap_or(true);
+        let type_matches = self.environment_types.is_empty()
+            || environment.environment_type.as_ref().is_some_and(|t| {
+                self.environment_types
+                    .iter()
+                    .any(|allowed| allowed.eq_ignore_ascii_case(t))
+            });
+
+        name_matches && type_matches
+    }
+}
+
+fn front_matter_error(msg: String) -> HoganError {
+    HoganError::InvalidConfiguration {
+        param: "front matter".to_string(),
+        msg,
     }
 }
 
 pub struct Template {
     pub path: PathBuf,
+    /// The template source with any front matter removed
     pub contents: String,
+    /// The template directory this template was found in
+    pub root: PathBuf,
+    /// Overrides the default output naming of replacing "template" with the environment name
+    pub output_pattern: Option<OutputPattern>,
+    pub front_matter: FrontMatter,
+    /// Overrides the escaping of {{value}} expressions set on the handlebars registry
+    pub escape: Option<EscapeMode>,
 }
 
 impl Template {
-    fn from_path_buf(path: PathBuf) -> Result<Template> {
+    /// Builds a template from its source, splitting off any front matter. The path is used for
+    /// output naming and should be within the root
+    pub fn new(root: &Path, path: PathBuf, source: &str) -> Result<Template> {
+        let (front_matter, contents) =
+            FrontMatter::parse(source).with_context(|| format!("Error reading {:?}", path))?;
+        let front_matter = front_matter.unwrap_or_default();
+
         Ok(Template {
-            path: path.clone(),
-            contents: fs::read_to_string(path)?,
+            path,
+            contents: contents.to_owned(),
+            root: root.to_owned(),
+            output_pattern: front_matter.output_pattern.clone(),
+            front_matter,
+            escape: None,
         })
     }
+
+    /// The escaping suited to the template's output, picked by its file extension
+    pub fn escape_for_extension(&self) -> EscapeMode {
+        EscapeMode::for_extension(&self.name_parts().1)
+    }
+
+    /// Whether the template should be rendered for the environment, according to its front matter
+    pub fn applies_to(&self, environment: &Environment) -> bool {
+        self.front_matter.applies_to(environment)
+    }
+
+    /// Splits the file name into the name with the template segment removed, and the extension
+    /// e.g. Web.template.config => (Web, config)
+    fn name_parts(&self) -> (String, String) {
+        let ext = self
+            .path
+            .extension()
+            .map(|e| e.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let file_stem = self
+            .path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let mut segments = file_stem.split('.').collect::<Vec<&str>>();
+        if let Some(i) = segments
+            .iter()
+            .position(|s| s.eq_ignore_ascii_case("template"))
+        {
+            segments.remove(i);
+        }
+
+        (segments.join("."), ext)
+    }
+
+    fn relative_dir(&self) -> PathBuf {
+        self.path
+            .parent()
+            .and_then(|p| p.strip_prefix(&self.root).ok())
+            .filter(|p| !p.as_os_str().is_empty())
+            .map(|p| p.to_owned())
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
+
+    /// Where the template renders to for an environment, relative to the templates directory
+    pub fn relative_output_path(&self, environment: &Environment) -> PathBuf {
+        match self.output_pattern {
+            Some(ref pattern) => pattern.relative_output_path(self, environment),
+            None => self
+                .path
+                .strip_prefix(&self.root)
+                .unwrap_or(&self.path)
+                .with_file_name(
+                    self.path
+                        .file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .replace("template", &environment.environment),
+                ),
+        }
+    }
+
+    pub fn output_path(&self, environment: &Environment) -> PathBuf {
+        self.root.join(self.relative_output_path(environment))
+    }
+}
+
+/// Key of the reserved data hogan adds to the root of the render data, read in templates as
+/// {{hogan.environment}}, or {{@root.hogan.environment}} within a block. Handlebars @ variables
+/// can't be nested or seen from inside blocks, so the data is not exposed as @hogan. It is only
+/// added for templates referencing it. A config defining this key at its root keeps its own value
+pub const RESERVED_KEY: &str = "hogan";
+
+/// Metadata about a render, added to the render data under the reserved key
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderMetadata {
+    pub environment: String,
+    pub environment_type: Option<String>,
+    /// Commit of the configs, when they are a git repository
+    pub sha: Option<String>,
+    /// Path of the template relative to the templates directory, when rendering from a file
+    pub template: Option<String>,
+}
+
+impl RenderMetadata {
+    pub fn new(environment: &Environment, sha: Option<&str>, template: Option<&Path>) -> Self {
+        RenderMetadata {
+            environment: environment.environment.clone(),
+            environment_type: environment.environment_type.clone(),
+            sha: sha.map(|s| s.to_owned()),
+            template: template.map(|t| t.to_string_lossy().replace('\\', "/")),
+        }
+    }
+
+    /// The data to render the template source with. The metadata is only added, under the
+    /// reserved key, when the template or its partials reference it, so templates serializing or
+    /// iterating the whole config are unaffected
+    pub fn render_data(&self, handlebars: &Handlebars, source: &str, config_data: &Value) -> Value {
+        if crate::vars::uses_render_metadata(handlebars, source) {
+            self.add_to(config_data)
+        } else {
+            config_data.clone()
+        }
+    }
+
+    /// Copies the config with the metadata added under the reserved key. A config value of the
+    /// same name shadows the metadata. The environment's own config is left untouched
+    pub fn add_to(&self, config_data: &Value) -> Value {
+        let mut data = match config_data {
+            Value::Null => Value::Object(Map::new()),
+            data => data.clone(),
+        };
+        if let Value::Object(ref mut map) = data {
+            if map.contains_key(RESERVED_KEY) {
+                warn!(
+                    "The config for {} defines the key '{}', render metadata is not available to its templates",
+                    self.environment, RESERVED_KEY
+                );
+            } else {
+                map.insert(
+                    RESERVED_KEY.to_owned(),
+                    serde_json::to_value(self).unwrap_or_default(),
+                );
+            }
+        }
+        data
+    }
 }
 
 impl Template {
-    pub fn render(&self, handlebars: &Handlebars, environment: &Environment) -> Result<Rendered> {
+    /// Renders the template for the environment, with the configs' commit available to the
+    /// template as {{hogan.sha}}
+    pub fn render(
+        &self,
+        handlebars: &Handlebars,
+        environment: &Environment,
+        sha: Option<&str>,
+    ) -> Result<Rendered> {
         let mut buf = Cursor::new(Vec::new());
-        handlebars
-            .render_template_to_write(&self.contents, &environment.config_data, &mut buf)
-            .with_context(|| {
-                format!(
-                    "Error when rendering file:{:?} env:{}",
-                    self.path.file_name(),
-                    environment.environment,
-                )
-            })?;
+        let data = self.render_data(handlebars, environment, sha);
+        let mut render = || {
+            handlebars
+                .render_template_to_write(&self.contents, &data, &mut buf)
+                .map_err(anyhow::Error::from)
+        };
+        match self.escape {
+            Some(mode) => with_escape_mode(mode, render),
+            None => render(),
+        }
+        .with_context(|| {
+            format!(
+                "Error when rendering file:{:?} env:{}",
+                self.path.file_name(),
+                environment.environment,
+            )
+        })?;
 
         Ok(Rendered {
-            path: self.path.clone().with_file_name(
-                self.path
-                    .file_name()
-                    .unwrap()
-                    .to_string_lossy()
-                    .replace("template", &environment.environment),
-            ),
+            path: self.output_path(environment),
             contents: buf.into_inner(),
+            mode: self.mode(),
         })
     }
 
+    /// The environment's config with the render metadata added
+    pub fn render_data(
+        &self,
+        handlebars: &Handlebars,
+        environment: &Environment,
+        sha: Option<&str>,
+    ) -> Value {
+        let template = self.path.strip_prefix(&self.root).unwrap_or(&self.path);
+        RenderMetadata::new(environment, sha, Some(template)).render_data(
+            handlebars,
+            &self.contents,
+            &environment.config_data,
+        )
+    }
+
+    /// The mode from the front matter, otherwise the permission bits of the template file
+    pub fn mode(&self) -> Option<u32> {
+        self.front_matter.mode.or_else(|| file_mode(&self.path))
+    }
+
+    /// Renders the template for every environment it applies to into a zip archive, with each
+    /// entry at its output path relative to the templates directory
     pub fn render_to_zip(
         &self,
         handlebars: &Handlebars,
         environments: &[Environment],
+        sha: Option<&str>,
     ) -> Result<Vec<u8>> {
-        let options = FileOptions::default().compression_method(Stored);
-        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let mut bundle = Bundle::new();
 
-        for environment in environments {
-            let rendered = self.render(&handlebars, &environment)?;
-            zip.start_file(
-                rendered.path.file_name().unwrap().to_string_lossy(),
-                options,
-            )?;
-            zip.write_all(&rendered.contents)?;
+        for environment in environments.iter().filter(|e| self.applies_to(e)) {
+            let rendered = self.render(handlebars, environment, sha)?;
+            bundle.add(&self.relative_output_path(environment), &rendered.contents)?;
         }
 
-        Ok(zip.finish()?.into_inner())
+        bundle.finish()
     }
 }
 
+lazy_static! {
+    static ref STRICT_ERROR_REGEX: Regex =
+        Regex::new(r#"^Variable "(.*)" not found in strict mode\.$"#).unwrap();
+}
+
+/// A value referenced by a template which does not exist in an environment's config
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MissingValue {
+    /// Name of the partial the reference is in, if it's not in the template itself
+    pub partial: Option<String>,
+    /// Line in the template file, or the partial, counting from 1
+    pub line: usize,
+    pub column: usize,
+    /// The path as written in the template, e.g. Region.Key
+    pub path: String,
+}
+
+impl Template {
+    /// Renders the template in strict mode, collecting every missing value instead of stopping at
+    /// the first. Each missing reference is commented out and the render retried, so only the
+    /// first missing value within a partial can be found. Other render errors are returned
+    pub fn find_missing(
+        &self,
+        handlebars: &Handlebars,
+        environment: &Environment,
+        sha: Option<&str>,
+    ) -> Result<Vec<MissingValue>> {
+        let data = self.render_data(handlebars, environment, sha);
+        let mut source = self.contents.clone();
+        let mut missing = Vec::new();
+
+        loop {
+            let error = match handlebars.render_template(&source, &data) {
+                Ok(_) => return Ok(missing),
+                Err(TemplateRenderError::RenderError(e)) => e,
+                Err(e) => return Err(e.into()),
+            };
+            let (path, line, column) = match (
+                STRICT_ERROR_REGEX.captures(&error.desc),
+                error.line_no,
+                error.column_no,
+            ) {
+                (Some(captures), Some(line), Some(column)) => {
+                    (captures[1].to_owned(), line, column)
+                }
+                _ => return Err(error.into()),
+            };
+
+            if error.template_name.is_some() {
+                missing.push(MissingValue {
+                    partial: error.template_name,
+                    line,
+                    column,
+                    path,
+                });
+                return Ok(missing);
+            }
+
+            source = comment_out_expression(&source, line, column).ok_or(error)?;
+            missing.push(MissingValue {
+                partial: None,
+                line: line + self.front_matter.lines,
+                column,
+                path,
+            });
+        }
+    }
+}
+
+/// Replaces the expression starting at the line and column with a comment of the same length, so
+/// the positions of the expressions after it don't change
+fn comment_out_expression(source: &str, line: usize, column: usize) -> Option<String> {
+    let line_start = source
+        .split_inclusive('\n')
+        .take(line.checked_sub(1)?)
+        .map(str::len)
+        .sum::<usize>();
+    let start = line_start
+        + source[line_start..]
+            .char_indices()
+            .nth(column.checked_sub(1)?)?
+            .0;
+
+    let expression = &source[start..];
+    let close = if expression.starts_with("{{{") {
+        "}}}"
+    } else if expression.starts_with("{{") {
+        "}}"
+    } else {
+        return None;
+    };
+    let end = start + expression.find(close)? + close.len();
+
+    let filler = source[start..end]
+        .chars()
+        .skip(3)
+        .take(source[start..end].chars().count() - 5)
+        .map(|c| if c == '\n' { '\n' } else { ' ' })
+        .collect::<String>();
+    Some(format!(
+        "{}{{{{!{}}}}}{}",
+        &source[..start],
+        filler,
+        &source[end..]
+    ))
+}
+
+/// Zip archive of rendered templates
+pub struct Bundle {
+    zip: ZipWriter<Cursor<Vec<u8>>>,
+    entries: usize,
+}
+
+impl Default for Bundle {
+    fn default() -> Self {
+        Bundle::new()
+    }
+}
+
+impl Bundle {
+    pub fn new() -> Bundle {
+        Bundle {
+            zip: ZipWriter::new(Cursor::new(Vec::new())),
+            entries: 0,
+        }
+    }
+
+    /// Adds a file at the relative path, using / as the separator regardless of platform
+    pub fn add(&mut self, path: &Path, contents: &[u8]) -> Result<()> {
+        let name = path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        self.zip
+            .start_file(name, FileOptions::default().compression_method(Stored))?;
+        self.zip.write_all(contents)?;
+        self.entries += 1;
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries == 0
+    }
+
+    pub fn finish(mut self) -> Result<Vec<u8>> {
+        Ok(self.zip.finish()?.into_inner())
+    }
+}
+
+#[cfg(unix)]
+pub fn file_mode(path: &Path) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::metadata(path)
+        .ok()
+        .map(|m| m.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+pub fn file_mode(_path: &Path) -> Option<u32> {
+    None
+}
+
 pub struct Rendered {
     pub path: PathBuf,
     pub contents: Vec<u8>,
+    /// Unix permission bits to write the file with, taken from the template
+    pub mode: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderStatus {
+    Created,
+    Changed,
+    Unchanged,
+}
+
+impl fmt::Display for RenderStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RenderStatus::Created => f.pad("new"),
+            RenderStatus::Changed => f.pad("changed"),
+            RenderStatus::Unchanged => f.pad("unchanged"),
+        }
+    }
+}
+
+impl Rendered {
+    /// Compares the rendered contents with what is currently on disk at the output path
+    pub fn status(&self) -> Result<RenderStatus> {
+        match fs::read(&self.path) {
+            Ok(ref existing) if existing == &self.contents => Ok(RenderStatus::Unchanged),
+            Ok(_) => Ok(RenderStatus::Changed),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(RenderStatus::Created),
+            Err(e) => Err(e).with_context(|| format!("Error reading {:?}", self.path)),
+        }
+    }
+
+    /// Builds a unified diff from the file currently on disk to the rendered contents. A missing
+    /// file is diffed as empty. Returns None when the contents are identical
+    pub fn unified_diff(&self) -> Result<Option<String>> {
+        let (existing, from_header) = match fs::read(&self.path) {
+            Ok(existing) => (existing, format!("a/{}", self.path.display())),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => (Vec::new(), "/dev/null".to_string()),
+            Err(e) => return Err(e).with_context(|| format!("Error reading {:?}", self.path)),
+        };
+
+        if existing == self.contents {
+            return Ok(None);
+        }
+
+        let existing = String::from_utf8_lossy(&existing);
+        let contents = String::from_utf8_lossy(&self.contents);
+        let diff = TextDiff::from_lines(existing.as_ref(), contents.as_ref())
+            .unified_diff()
+            .header(&from_header, &format!("b/{}", self.path.display()))
+            .to_string();
+
+        Ok(Some(diff))
+    }
 }
 
 #[cfg(test)]
@@ -109,12 +731,370 @@ mod tests {
     fn test_find_all_templates() {
         let template_dir =
             TemplateDir::new(PathBuf::from("tests/fixtures/projects/templates")).unwrap();
-        let templates = template_dir.find(
+        let (templates, invalid) = template_dir.find(
             RegexBuilder::new("^[^.]*(\\w+\\.)*template([-.].+)?\\.(config|ya?ml|properties)$")
                 .case_insensitive(true)
                 .build()
                 .unwrap(),
         );
-        assert_eq!(templates.len(), 6)
+        assert_eq!(templates.len(), 6);
+        assert!(invalid.is_empty());
+    }
+
+    #[test]
+    fn test_find_yaml_document_and_invalid_front_matter() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::write(
+            temp_dir.path().join("app.template.yaml"),
+            "---\nRegion: {{Region.Key}}\n",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join("bad.template.yaml"),
+            "---hogan\nunknown: value\n---\nRegion: {{Region.Key}}\n",
+        )
+        .unwrap();
+
+        let template_dir = TemplateDir::new(temp_dir.path().to_owned()).unwrap();
+        let (templates, invalid) = template_dir.find(build_regex("template\\.yaml$").unwrap());
+
+        assert_eq!(templates.len(), 1);
+        assert_eq!(templates[0].contents, "---\nRegion: {{Region.Key}}\n");
+        assert_eq!(invalid.len(), 1);
+        assert_eq!(invalid[0].path, temp_dir.path().join("bad.template.yaml"));
+        assert!(format!("{:#}", invalid[0].error).contains("Unknown key unknown"));
+    }
+
+    fn environment() -> Environment {
+        Environment {
+            environment: "TEST".to_string(),
+            environment_type: Some("alpha".to_string()),
+            config_data: serde_json::Value::Null,
+        }
+    }
+
+    fn template(path: &str) -> Template {
+        Template {
+            path: PathBuf::from("templates").join(path),
+            contents: String::new(),
+            root: PathBuf::from("templates"),
+            output_pattern: None,
+            front_matter: FrontMatter::default(),
+            escape: None,
+        }
+    }
+
+    #[test]
+    fn test_default_output_path() {
+        assert_eq!(
+            template("project-1/Web.template.config").output_path(&environment()),
+            PathBuf::from("templates/project-1/Web.TEST.config")
+        );
+        assert_eq!(
+            template("project-4/template.yaml").output_path(&environment()),
+            PathBuf::from("templates/project-4/TEST.yaml")
+        );
+    }
+
+    #[test]
+    fn test_output_pattern() {
+        let pattern: OutputPattern = "out/{env}/{dir}/{stem}.{ext}".parse().unwrap();
+        let mut web = template("project-1/Web.template.config");
+        web.output_pattern = Some(pattern.clone());
+        assert_eq!(
+            web.output_path(&environment()),
+            PathBuf::from("templates/out/TEST/project-1/Web.config")
+        );
+
+        let mut templated = template("templates.template.yaml");
+        templated.output_pattern = Some(pattern);
+        assert_eq!(
+            templated.output_path(&environment()),
+            PathBuf::from("templates/out/TEST/templates.yaml")
+        );
+
+        let mut typed = template("project-1/Web.template.config");
+        typed.output_pattern = Some("{dir}/{envType}/{stem}.{env}.{ext}".parse().unwrap());
+        assert_eq!(
+            typed.output_path(&environment()),
+            PathBuf::from("templates/project-1/alpha/Web.TEST.config")
+        );
+    }
+
+    #[test]
+    fn test_relative_output_path() {
+        let mut web = template("project-1/Web.template.config");
+        assert_eq!(
+            web.relative_output_path(&environment()),
+            PathBuf::from("project-1/Web.TEST.config")
+        );
+
+        web.output_pattern = Some("{env}/{dir}/{stem}.{ext}".parse().unwrap());
+        assert_eq!(
+            web.relative_output_path(&environment()),
+            PathBuf::from("TEST/project-1/Web.config")
+        );
+    }
+
+    #[test]
+    fn test_front_matter() {
+        let source = "---hogan\nenvironments: TEST|PROD.*\n# Only web servers\nenvironmentTypes: Alpha, beta\noutput: {env}/{stem}.{ext}\nmode: 0600\n---\n<config />\n";
+        let template = Template::new(
+            Path::new("templates"),
+            PathBuf::from("templates/Web.template.config"),
+            source,
+        )
+        .unwrap();
+
+        assert_eq!(template.contents, "<config />\n");
+        assert_eq!(template.front_matter.mode, Some(0o600));
+        assert_eq!(
+            template.output_path(&environment()),
+            PathBuf::from("templates/TEST/Web.config")
+        );
+
+        let env = |name: &str, env_type: Option<&str>| Environment {
+            environment: name.to_string(),
+            environment_type: env_type.map(|t| t.to_string()),
+            config_data: serde_json::Value::Null,
+        };
+        assert!(template.applies_to(&env("test", Some("alpha"))));
+        assert!(template.applies_to(&env("PROD2", Some("BETA"))));
+        assert!(!template.applies_to(&env("TEST2", Some("alpha"))));
+        assert!(!template.applies_to(&env("TEST", Some("gamma"))));
+        assert!(!template.applies_to(&env("TEST", None)));
+    }
+
+    #[test]
+    fn test_without_front_matter() {
+        let source = "<config>\n---\n</config>\n";
+        let (front_matter, body) = FrontMatter::parse(source).unwrap();
+        assert!(front_matter.is_none());
+        assert_eq!(body, source);
+        assert!(FrontMatter::default().applies_to(&environment()));
+
+        // A YAML document start marker isn't front matter
+        let yaml = "---\nRegion: {{Region.Key}}\n---\nOther: document\n";
+        let (front_matter, body) = FrontMatter::parse(yaml).unwrap();
+        assert!(front_matter.is_none());
+        assert_eq!(body, yaml);
+    }
+
+    #[test]
+    fn test_invalid_front_matter() {
+        assert!(FrontMatter::parse("---hogan\nenvironments: TEST\n<config />").is_err());
+        assert!(FrontMatter::parse("---hogan\nunknown: value\n---\n").is_err());
+        assert!(FrontMatter::parse("---hogan\nenvironments: (\n---\n").is_err());
+        assert!(FrontMatter::parse("---hogan\noutput: {name}\n---\n").is_err());
+        assert!(FrontMatter::parse("---hogan\nmode: rwx\n---\n").is_err());
+        assert!(FrontMatter::parse("---hogan\nno separator\n---\n").is_err());
+    }
+
+    #[test]
+    fn test_render_metadata() {
+        let template = Template::new(
+            Path::new("templates"),
+            PathBuf::from("templates/project-1/Web.template.config"),
+            "{{hogan.environment}} {{hogan.environmentType}} {{hogan.sha}} {{hogan.template}}\n\
+             {{#each Items}}{{@root.hogan.environment}}-{{this}};{{/each}}",
+        )
+        .unwrap();
+        let mut environment = environment();
+        environment.config_data = serde_json::json!({"Items": [1, 2]});
+
+        let handlebars = crate::transform::handlebars(true);
+        let rendered = template
+            .render(&handlebars, &environment, Some("abc123"))
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(rendered.contents).unwrap(),
+            "TEST alpha abc123 project-1/Web.template.config\nTEST-1;TEST-2;"
+        );
+        assert_eq!(
+            environment.config_data,
+            serde_json::json!({"Items": [1, 2]})
+        );
+
+        // Templates not referencing the metadata render the config as it is
+        let serialized = Template::new(
+            Path::new("."),
+            PathBuf::from("t"),
+            "{{to-json this}}|{{#each this}}{{@key}};{{/each}}",
+        )
+        .unwrap();
+        let rendered = serialized
+            .render(&handlebars, &environment, Some("abc123"))
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(rendered.contents).unwrap(),
+            r#"{"Items":[1,2]}|Items;"#
+        );
+
+        // A config defining the reserved key keeps its value
+        let shadowed = Template::new(Path::new("."), PathBuf::from("t"), "{{hogan}}").unwrap();
+        environment.config_data = serde_json::json!({"Items": [1, 2], "hogan": "mine"});
+        let rendered = shadowed
+            .render(&handlebars, &environment, Some("abc123"))
+            .unwrap();
+        assert_eq!(rendered.contents, b"mine");
+
+        environment.config_data = serde_json::Value::Null;
+        let metadata = RenderMetadata::new(&environment, None, None);
+        assert_eq!(
+            metadata.add_to(&environment.config_data),
+            serde_json::json!({"hogan": {
+                "environment": "TEST",
+                "environmentType": "alpha",
+                "sha": null,
+                "template": null,
+            }})
+        );
+    }
+
+    #[test]
+    fn test_render_to_zip() {
+        let template = Template::new(
+            Path::new("templates"),
+            PathBuf::from("templates/project-1/Web.template.config"),
+            "---hogan\nenvironments: TEST.*\n---\n{{Region.Key}}",
+        )
+        .unwrap();
+        let env = |name: &str| Environment {
+            environment: name.to_string(),
+            environment_type: None,
+            config_data: serde_json::json!({ "Region": { "Key": name } }),
+        };
+
+        let handlebars = crate::transform::handlebars(false);
+        let zipped = template
+            .render_to_zip(&handlebars, &[env("TEST"), env("PROD"), env("TEST2")], None)
+            .unwrap();
+
+        let mut archive = zip::ZipArchive::new(Cursor::new(zipped)).unwrap();
+        let mut names = archive.file_names().collect::<Vec<_>>();
+        names.sort_unstable();
+        assert_eq!(
+            names,
+            vec!["project-1/Web.TEST.config", "project-1/Web.TEST2.config"]
+        );
+
+        let mut contents = String::new();
+        std::io::Read::read_to_string(
+            &mut archive.by_name("project-1/Web.TEST2.config").unwrap(),
+            &mut contents,
+        )
+        .unwrap();
+        assert_eq!(contents, "TEST2");
+    }
+
+    #[test]
+    fn test_render_status() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("Web.TEST.config");
+        let rendered = Rendered {
+            path: path.clone(),
+            contents: b"rendered".to_vec(),
+            mode: None,
+        };
+
+        assert_eq!(rendered.status().unwrap(), RenderStatus::Created);
+        fs::write(&path, "stale").unwrap();
+        assert_eq!(rendered.status().unwrap(), RenderStatus::Changed);
+        fs::write(&path, "rendered").unwrap();
+        assert_eq!(rendered.status().unwrap(), RenderStatus::Unchanged);
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("Web.TEST.config");
+        let rendered = Rendered {
+            path: path.clone(),
+            contents: b"one\ntwo\nthree\n".to_vec(),
+            mode: None,
+        };
+
+        let created = rendered.unified_diff().unwrap().unwrap();
+        assert!(created.starts_with("--- /dev/null\n"));
+        assert!(created.contains("+one\n+two\n+three\n"));
+
+        fs::write(&path, "one\n2\nthree\n").unwrap();
+        let changed = rendered.unified_diff().unwrap().unwrap();
+        assert!(changed.contains(&format!("--- a/{}\n", path.display())));
+        assert!(changed.contains(&format!("+++ b/{}\n", path.display())));
+        assert!(changed.contains("@@ -1,3 +1,3 @@\n one\n-2\n+two\n three\n"));
+
+        fs::write(&path, "one\ntwo\nthree\n").unwrap();
+        assert!(rendered.unified_diff().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_invalid_output_pattern() {
+        assert!("out/{environment}/{stem}.{ext}"
+            .parse::<OutputPattern>()
+            .is_err());
+        assert!("".parse::<OutputPattern>().is_err());
+        assert!("../{env}/{stem}.{ext}".parse::<OutputPattern>().is_err());
+        assert!("out/../../{stem}.{ext}".parse::<OutputPattern>().is_err());
+        assert!("/etc/{stem}.{ext}".parse::<OutputPattern>().is_err());
+        assert!("./out/{env}/{stem}.{ext}".parse::<OutputPattern>().is_ok());
+        assert!(FrontMatter::parse("---hogan\noutput: ../{stem}.{ext}\n---\n").is_err());
+    }
+
+    #[test]
+    fn test_find_missing() {
+        let source = "---hogan\nmode: 0600\n---\nhost: {{Host}}\nport: {{Port}} {{{Region.Key}}}\n{{#each Items}}\n  - {{Name}} {{Value}}\n{{/each}}\n{{> logging}}\n";
+        let template = Template::new(
+            Path::new("templates"),
+            PathBuf::from("templates/Web.template.yaml"),
+            source,
+        )
+        .unwrap();
+        let mut environment = environment();
+        environment.config_data = serde_json::json!({
+            "Port": 80,
+            "Items": [{"Name": "a", "Value": 1}, {"Name": "b"}],
+        });
+
+        let mut handlebars = crate::transform::handlebars(true);
+        handlebars
+            .register_partial("logging", "level: {{Logging.Level}} {{Logging.File}}")
+            .unwrap();
+
+        let missing = |partial: Option<&str>, line, column, path: &str| MissingValue {
+            partial: partial.map(str::to_owned),
+            line,
+            column,
+            path: path.to_owned(),
+        };
+        assert_eq!(
+            template
+                .find_missing(&handlebars, &environment, None)
+                .unwrap(),
+            vec![
+                missing(None, 4, 7, "Host"),
+                missing(None, 5, 16, "Region.Key"),
+                missing(None, 7, 14, "Value"),
+                missing(Some("logging"), 1, 8, "Logging.Level"),
+            ]
+        );
+
+        environment.config_data = serde_json::json!({
+            "Host": "localhost",
+            "Port": 80,
+            "Region": {"Key": "us-east-1"},
+            "Items": [],
+            "Logging": {"Level": "info", "File": "app.log"},
+        });
+        assert!(template
+            .find_missing(&handlebars, &environment, None)
+            .unwrap()
+            .is_empty());
+
+        let mut broken = template;
+        broken.contents = "{{#if Host}}".to_owned();
+        assert!(broken
+            .find_missing(&handlebars, &environment, None)
+            .is_err());
     }
 }
@@ -0,0 +1,37 @@
+//! Helpers for tests which need a git repository of configs. The module is compiled into both the
+//! library and the binary, so their tests share one copy
+
+use fs_extra::dir;
+use git2::{IndexAddOption, Repository, Signature};
+use std::path::Path;
+
+/// Copies the fixture configs into the path and commits them to a new repository
+pub(crate) fn init_config_repo(path: &Path) -> Repository {
+    fs_extra::copy_items(&["tests/fixtures/configs"], path, &dir::CopyOptions::new()).unwrap();
+    let repo = Repository::init(path).unwrap();
+    commit_all(&repo, "Initial configs");
+    repo
+}
+
+/// Commits every file in the working directory, returning the new commit's SHA
+pub(crate) fn commit_all(repo: &Repository, message: &str) -> String {
+    let mut index = repo.index().unwrap();
+    index
+        .add_all(["*"].iter(), IndexAddOption::DEFAULT, None)
+        .unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = Signature::now("Hogan", "hogan@example.com").unwrap();
+    let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    let parents = parent.iter().collect::<Vec<_>>();
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &parents,
+    )
+    .unwrap()
+    .to_string()
+}
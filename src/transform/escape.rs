@@ -0,0 +1,174 @@
+use std::cell::Cell;
+use std::fmt::Write;
+
+/// How `{{value}}` expressions are escaped. `{{{value}}}` is never escaped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EscapeMode {
+    /// The HTML entities hogan has always used, see `old_escape_html`
+    Html,
+    /// For values inside a double quoted JSON string
+    Json,
+    /// For values inside a double quoted YAML string, as with the yaml-string helper
+    Yaml,
+    /// For .properties values
+    Properties,
+}
+
+impl EscapeMode {
+    /// The escaping suited to a template's output, picked by the template's file extension.
+    /// Anything not recognised keeps the HTML escaping
+    pub fn for_extension(extension: &str) -> EscapeMode {
+        match extension.to_lowercase().as_str() {
+            "json" => EscapeMode::Json,
+            "yaml" | "yml" => EscapeMode::Yaml,
+            "properties" => EscapeMode::Properties,
+            _ => EscapeMode::Html,
+        }
+    }
+
+    pub fn escape(self, s: &str) -> String {
+        match self {
+            EscapeMode::Html => super::old_escape_html(s),
+            EscapeMode::Json | EscapeMode::Yaml => json_string(s),
+            EscapeMode::Properties => properties_value(s),
+        }
+    }
+}
+
+thread_local! {
+    static ESCAPE_MODE: Cell<EscapeMode> = const { Cell::new(EscapeMode::Html) };
+}
+
+/// Runs the closure with `{{value}}` expressions escaped for the mode. The handlebars registry
+/// only holds one escape function, so the mode is kept per thread for the length of the render
+pub fn with_escape_mode<T>(mode: EscapeMode, f: impl FnOnce() -> T) -> T {
+    let previous = ESCAPE_MODE.with(|m| m.replace(mode));
+    let result = f();
+    ESCAPE_MODE.with(|m| m.set(previous));
+    result
+}
+
+/// The escape function registered by `handlebars`, escaping for the mode of the current render
+pub fn escape(s: &str) -> String {
+    ESCAPE_MODE.with(|m| m.get()).escape(s)
+}
+
+/// Escapes a value for an XML attribute, in either single or double quotes
+pub fn xml_attr(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Parsers normalize literal whitespace in attributes to spaces
+            '\n' => escaped.push_str("&#10;"),
+            '\r' => escaped.push_str("&#13;"),
+            '\t' => escaped.push_str("&#9;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escapes a value for the text between XML tags
+pub fn xml_text(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escapes a value for the inside of a double quoted JSON string, without adding the quotes
+pub fn json_string(s: &str) -> String {
+    let quoted = serde_json::to_string(s).unwrap_or_default();
+    quoted[1..quoted.len() - 1].to_owned()
+}
+
+/// Escapes a value for a .properties file, as read by java.util.Properties. Characters outside
+/// printable ASCII are written as \uXXXX since the file is read as ISO-8859-1
+pub fn properties_value(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for (index, c) in s.chars().enumerate() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\x0c' => escaped.push_str("\\f"),
+            '=' | ':' | '#' | '!' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            // Leading whitespace is otherwise dropped
+            ' ' if index == 0 => escaped.push_str("\\ "),
+            c if !(' '..='~').contains(&c) => {
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    let _ = write!(escaped, "\\u{:04X}", unit);
+                }
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Quotes a value as a single POSIX shell word. Values made only of characters the shell treats
+/// literally are left as they are
+pub fn shell_quote(s: &str) -> String {
+    let literal = !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_@%+=:,./-".contains(c));
+    if literal {
+        s.to_owned()
+    } else {
+        format!("'{}'", s.replace('\'', "'\\''"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_escape_functions() {
+        assert_eq!(
+            xml_attr("a<b>&\"c\"\n'd'"),
+            "a&lt;b&gt;&amp;&quot;c&quot;&#10;&apos;d&apos;"
+        );
+        assert_eq!(xml_text("a<b>&\"c\""), "a&lt;b&gt;&amp;\"c\"");
+        assert_eq!(json_string("C:\\dir \"x\"\n"), "C:\\\\dir \\\"x\\\"\\n");
+        assert_eq!(
+            properties_value(" key=value: #1! C:\\dir\n\u{e9}\u{1f600}"),
+            "\\ key\\=value\\: \\#1\\! C\\:\\\\dir\\n\\u00E9\\uD83D\\uDE00"
+        );
+        assert_eq!(shell_quote("/usr/local/bin"), "/usr/local/bin");
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("it's $HOME"), "'it'\\''s $HOME'");
+    }
+
+    #[test]
+    fn test_escape_mode() {
+        assert_eq!(EscapeMode::for_extension("YML"), EscapeMode::Yaml);
+        assert_eq!(EscapeMode::for_extension("config"), EscapeMode::Html);
+        assert_eq!(
+            EscapeMode::for_extension("properties"),
+            EscapeMode::Properties
+        );
+
+        assert_eq!(escape("\"a\" & b"), "&quot;a&quot; &amp; b");
+        assert_eq!(
+            with_escape_mode(EscapeMode::Yaml, || escape("\"a\" & b")),
+            "\\\"a\\\" & b"
+        );
+        assert_eq!(escape("\"a\" & b"), "&quot;a&quot; &amp; b");
+    }
+}
@@ -0,0 +1,63 @@
+use crate::transform::{check_arity, is_truthy, render_condition};
+use handlebars::*;
+
+#[derive(Clone, Copy)]
+pub struct AndHelper;
+
+impl HelperDef for AndHelper {
+    // Logical AND of two or more params
+    // Usage: {{#and ConfigEnabled (eq Region.Key "TEST")}}...{{/and}}
+    fn call<'reg: 'rc, 'rc, 'ctx>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        r: &'reg Handlebars,
+        ctx: &'ctx Context,
+        rc: &mut RenderContext<'reg, 'ctx>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        check_arity(h, "and")?;
+        let comparison = h.params().iter().all(|p| is_truthy(p.value()));
+        render_condition(comparison, h, r, ctx, rc, out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::helper_equal::EqualHelper;
+    use crate::transform::test::{render_fixture, test_error_against_configs};
+
+    #[test]
+    fn test_and() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("eq", Box::new(EqualHelper));
+        handlebars.register_helper("and", Box::new(AndHelper));
+
+        let templates = vec![
+            (
+                r#"{{#and ConfigEnabled (eq Region.Key "TEST")}}Foo{{/and}}"#,
+                "Foo",
+            ),
+            (
+                r#"{{#and ConfigEnabled (eq Region.Key "NO")}}{{else}}Bar{{/and}}"#,
+                "Bar",
+            ),
+            (
+                r#"{{#if (and Region.Key Memcache.Servers UpperCaseString)}}Foo{{/if}}"#,
+                "Foo",
+            ),
+            (r#"{{#if (and Region.Key Missing)}}Foo{{/if}}"#, ""),
+            (r#"{{and ConfigEnabled Region.Key}}"#, "true"),
+        ];
+
+        for (template, expected) in templates {
+            assert_eq!(render_fixture(&handlebars, template), expected);
+        }
+
+        test_error_against_configs(
+            &handlebars,
+            r#"{{#and ConfigEnabled}}Foo{{/and}}"#,
+            "'and' requires at least 2 parameters",
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use crate::transform::string_param;
+use handlebars::*;
+
+#[derive(Clone, Copy)]
+pub struct Base64DecodeHelper;
+
+impl HelperDef for Base64DecodeHelper {
+    // Decodes standard base64 to text. Values which don't decode to UTF-8 are an error
+    // Usage: {{base64-decode Secrets.Encoded}}
+    fn call<'reg: 'rc, 'rc, 'ctx>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars,
+        _: &'ctx Context,
+        _: &mut RenderContext<'reg, 'ctx>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        if let Some(encoded) = string_param(h, 0, "base64-decode")? {
+            let decoded = base64::decode(encoded.trim())
+                .ok()
+                .and_then(|bytes| String::from_utf8(bytes).ok())
+                .ok_or_else(|| {
+                    RenderError::new(format!(
+                        "Param is not base64 encoded text for helper \"base64-decode\": {:?}",
+                        encoded
+                    ))
+                })?;
+            out.write(&decoded)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::helper_encode::{base64_encode, EncodeHelper};
+    use crate::transform::test::{test_against_configs, test_error_against_configs};
+
+    #[test]
+    fn test_base64_decode() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("base64-decode", Box::new(Base64DecodeHelper));
+        handlebars.register_helper(
+            "base64-encode",
+            Box::new(EncodeHelper {
+                name: "base64-encode",
+                encode: base64_encode,
+            }),
+        );
+
+        test_against_configs(
+            &handlebars,
+            "{{base64-decode (base64-encode DB.Endpoint)}}",
+            "host-name\\TEST\"",
+        );
+        assert_eq!(
+            handlebars
+                .render_template(
+                    "{{base64-decode Encoded}}",
+                    &serde_json::json!({"Encoded": "VVBQRVJDQVNF\n"})
+                )
+                .unwrap(),
+            "UPPERCASE"
+        );
+
+        test_error_against_configs(
+            &handlebars,
+            "{{base64-decode Region.Key}}",
+            "Param is not base64 encoded text for helper \"base64-decode\": \"TEST\"",
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::transform::helper_join::render_items;
 use handlebars::*;
 use itertools::join;
 use serde_json::value::Value as Json;
@@ -23,30 +24,7 @@ impl HelperDef for CommaDelimitedListHelper {
         match h.template() {
             Some(template) => match *value.value() {
                 Json::Array(ref list) => {
-                    let mut render_list = Vec::new();
-
-                    for (i, item) in list.iter().enumerate() {
-                        let mut local_rc = rc.clone();
-                        let block_rc = local_rc.block_mut().unwrap();
-                        if let Some(inner_path) = value.context_path() {
-                            let block_path = block_rc.base_path_mut();
-                            block_path.append(&mut inner_path.to_owned());
-                            block_path.push(i.to_string());
-                        }
-
-                        if let Some(block_param) = h.block_param() {
-                            let mut new_block = BlockContext::new();
-                            let mut block_params = BlockParams::new();
-                            let param = block_param.to_owned();
-                            block_params.add_value(&param, to_json(item))?;
-                            new_block.set_block_params(block_params);
-                            local_rc.push_block(new_block);
-
-                            render_list.push(template.renders(r, ctx, &mut local_rc)?);
-                        } else {
-                            render_list.push(template.renders(r, ctx, &mut local_rc)?);
-                        }
-                    }
+                    let render_list = render_items(h, r, ctx, rc, template, value, list)?;
                     out.write(&join(&render_list, ","))?;
 
                     Ok(())
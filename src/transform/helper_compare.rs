@@ -0,0 +1,99 @@
+use crate::transform::{check_arity, render_condition, CompareFn};
+use handlebars::*;
+use serde_json::value::Value as Json;
+
+/// Orders two params, numbers numerically and strings lexically, e.g. {{#gt Replicas 1}}.
+/// Registered once per comparison, which accepts the ordering of the params. A missing param
+/// never compares true
+#[derive(Clone, Copy)]
+pub struct CompareHelper {
+    pub name: &'static str,
+    pub accept: CompareFn,
+}
+
+impl HelperDef for CompareHelper {
+    fn call<'reg: 'rc, 'rc, 'ctx>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        r: &'reg Handlebars,
+        ctx: &'ctx Context,
+        rc: &mut RenderContext<'reg, 'ctx>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        check_arity(h, self.name)?;
+        let lvalue = h.params()[0].value();
+        let rvalue = h.params()[1].value();
+
+        let ordering = match (lvalue, rvalue) {
+            (Json::Null, _) | (_, Json::Null) => None,
+            (Json::Number(l), Json::Number(r)) => match (l.as_i64(), r.as_i64()) {
+                (Some(l), Some(r)) => Some(l.cmp(&r)),
+                _ => l
+                    .as_f64()
+                    .zip(r.as_f64())
+                    .and_then(|(l, r)| l.partial_cmp(&r)),
+            },
+            (Json::String(l), Json::String(r)) => Some(l.cmp(r)),
+            _ => {
+                return Err(RenderError::new(format!(
+                    "Params can not be compared for helper \"{}\": {:?} and {:?}",
+                    self.name, lvalue, rvalue
+                )))
+            }
+        };
+
+        let comparison = ordering.is_some_and(self.accept);
+        render_condition(comparison, h, r, ctx, rc, out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::test::{render_fixture, test_error_against_configs};
+
+    #[test]
+    fn test_compare() {
+        let mut handlebars = Handlebars::new();
+        for (name, accept) in crate::transform::COMPARE_HELPERS {
+            handlebars.register_helper(
+                name,
+                Box::new(CompareHelper {
+                    name,
+                    accept: *accept,
+                }),
+            );
+        }
+
+        let templates = vec![
+            (r#"{{#gt 10 9}}Foo{{/gt}}"#, "Foo"),
+            (r#"{{#gt "10" "9"}}Foo{{else}}Bar{{/gt}}"#, "Bar"),
+            (r#"{{#gte 1.5 1.5}}Foo{{/gte}}"#, "Foo"),
+            (r#"{{#lt -1 1}}Foo{{/lt}}"#, "Foo"),
+            (r#"{{#lte 2 1.5}}Foo{{else}}Bar{{/lte}}"#, "Bar"),
+            (r#"{{#if (lt Region.Key "UAT")}}Foo{{/if}}"#, "Foo"),
+            (
+                r#"{{#if (gte Memcache.Servers.0.Port "1122")}}Foo{{/if}}"#,
+                "Foo",
+            ),
+            (r#"{{#if (gt Missing 1)}}Foo{{else}}Bar{{/if}}"#, "Bar"),
+            (r#"{{lt Missing 1}}"#, ""),
+            (r#"{{lt 1 2}}"#, "true"),
+        ];
+
+        for (template, expected) in templates {
+            assert_eq!(render_fixture(&handlebars, template), expected);
+        }
+
+        test_error_against_configs(
+            &handlebars,
+            r#"{{#gt 1}}Foo{{/gt}}"#,
+            "'gt' requires exactly 2 parameters",
+        );
+        test_error_against_configs(
+            &handlebars,
+            r#"{{#gt Memcache.Servers.0.Port 1000}}Foo{{/gt}}"#,
+            "Params can not be compared for helper \"gt\": String(\"1122\") and Number(1000)",
+        );
+    }
+}
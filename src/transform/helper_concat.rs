@@ -0,0 +1,57 @@
+use crate::transform::string_param;
+use handlebars::*;
+
+#[derive(Clone, Copy)]
+pub struct ConcatHelper;
+
+impl HelperDef for ConcatHelper {
+    // Joins the params into one string, skipping nulls
+    // Usage: {{concat Region.Key "-" Name}}
+    fn call<'reg: 'rc, 'rc, 'ctx>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars,
+        _: &'ctx Context,
+        _: &mut RenderContext<'reg, 'ctx>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        if h.params().is_empty() {
+            return Err(RenderError::new("Param not found for helper \"concat\""));
+        }
+
+        for index in 0..h.params().len() {
+            if let Some(s) = string_param(h, index, "concat")? {
+                out.write(&s)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::test::{render_fixture, test_against_configs};
+
+    #[test]
+    fn test_concat() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("concat", Box::new(ConcatHelper));
+
+        test_against_configs(
+            &handlebars,
+            "{{concat Region.Key Memcache.Servers.0.Port ConfigEnabled}}",
+            "TEST1122true",
+        );
+        assert_eq!(
+            render_fixture(
+                &handlebars,
+                "{{concat \"memcache-\" Region.Key \":\" Missing}}"
+            ),
+            "memcache-TEST:"
+        );
+        assert!(handlebars
+            .render_template("{{concat Region}}", &serde_json::json!({"Region": {}}))
+            .is_err());
+    }
+}
@@ -0,0 +1,85 @@
+use crate::transform::{check_arity, render_condition, string_param};
+use handlebars::*;
+use serde_json::value::Value as Json;
+
+#[derive(Clone, Copy)]
+pub struct ContainsHelper;
+
+impl HelperDef for ContainsHelper {
+    // Whether a string contains a substring, an array contains an item or an object has a key
+    // Usage: {{#contains DB.Endpoint ".internal"}}...{{/contains}}
+    fn call<'reg: 'rc, 'rc, 'ctx>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        r: &'reg Handlebars,
+        ctx: &'ctx Context,
+        rc: &mut RenderContext<'reg, 'ctx>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        check_arity(h, "contains")?;
+        let item = h.params()[1].value();
+
+        let comparison = match h.params()[0].value() {
+            Json::Null => false,
+            Json::Array(items) => items.contains(item),
+            Json::Object(map) => string_param(h, 1, "contains")?
+                .map(|key| map.contains_key(&key))
+                .unwrap_or(false),
+            _ => match (
+                string_param(h, 0, "contains")?,
+                string_param(h, 1, "contains")?,
+            ) {
+                (Some(value), Some(part)) => value.contains(&part),
+                _ => false,
+            },
+        };
+        render_condition(comparison, h, r, ctx, rc, out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::test::{render_fixture, test_error_against_configs};
+
+    #[test]
+    fn test_contains() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("contains", Box::new(ContainsHelper));
+
+        let templates = vec![
+            (r#"{{#contains DB.Endpoint "host"}}Foo{{/contains}}"#, "Foo"),
+            (
+                r#"{{#contains DB.Endpoint "PROD"}}{{else}}Bar{{/contains}}"#,
+                "Bar",
+            ),
+            (r#"{{#if (contains Region "Key")}}Foo{{/if}}"#, "Foo"),
+            (
+                r#"{{#if (contains Memcache.Servers.0 "Port")}}Foo{{/if}}"#,
+                "Foo",
+            ),
+            (r#"{{#if (contains Missing "Key")}}Foo{{/if}}"#, ""),
+            (r#"{{contains ConfigEnabled "ru"}}"#, "true"),
+        ];
+
+        for (template, expected) in templates {
+            assert_eq!(render_fixture(&handlebars, template), expected);
+        }
+
+        assert_eq!(
+            handlebars
+                .render_template(
+                    r#"{{#contains Hosts "b"}}Foo{{/contains}}"#,
+                    &serde_json::json!({"Hosts": ["a", "b"]})
+                )
+                .unwrap(),
+            "Foo"
+        );
+
+        test_error_against_configs(
+            &handlebars,
+            r#"{{#contains Region.Key}}Foo{{/contains}}"#,
+            "'contains' requires exactly 2 parameters",
+        );
+    }
+}
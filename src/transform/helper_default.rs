@@ -0,0 +1,68 @@
+use handlebars::*;
+use serde_json::value::Value as Json;
+
+#[derive(Clone, Copy)]
+pub struct DefaultHelper;
+
+impl HelperDef for DefaultHelper {
+    // Returns the first param which isn't null or an empty string, escaped like any other value.
+    // As a subexpression arrays and objects are kept as they are
+    // Usage: {{default Region.Name "us-east-1"}} {{#each (default Hosts Defaults.Hosts)}}
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<Option<ScopedJson<'reg, 'rc>>, RenderError> {
+        if h.params().len() < 2 {
+            return Err(RenderError::new("'default' requires at least 2 parameters"));
+        }
+
+        let value = h
+            .params()
+            .iter()
+            .map(|p| p.value())
+            .find(|v| match v {
+                Json::Null => false,
+                Json::String(s) => !s.is_empty(),
+                _ => true,
+            })
+            .cloned()
+            .unwrap_or(Json::Null);
+        Ok(Some(ScopedJson::Derived(value)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::helper_trim::TrimHelper;
+    use crate::transform::test::{render_fixture, test_against_configs};
+
+    #[test]
+    fn test_default() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("default", Box::new(DefaultHelper));
+        handlebars.register_helper("trim", Box::new(TrimHelper));
+
+        test_against_configs(&handlebars, "{{default Missing Region.Key}}", "TEST");
+        test_against_configs(
+            &handlebars,
+            "{{default DB.Endpoint Region.Key}}",
+            "host-name\\TEST&quot;",
+        );
+        test_against_configs(
+            &handlebars,
+            "{{#each (default Missing Memcache.Servers)}}{{Port}}{{/each}}",
+            "112211221122",
+        );
+        assert_eq!(
+            render_fixture(&handlebars, "{{default (trim Missing) \"fallback\"}}"),
+            "fallback"
+        );
+        assert!(handlebars
+            .render_template("{{default Region}}", &serde_json::json!({}))
+            .is_err());
+    }
+}
@@ -0,0 +1,60 @@
+use crate::transform::{check_arity, render_condition};
+use handlebars::*;
+use serde_json::value::Value as Json;
+
+#[derive(Clone, Copy)]
+pub struct EmptyHelper;
+
+impl HelperDef for EmptyHelper {
+    // Whether a value is missing, or an empty string, array or object
+    // Usage: {{#empty Memcache.Servers}}...{{else}}...{{/empty}}
+    fn call<'reg: 'rc, 'rc, 'ctx>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        r: &'reg Handlebars,
+        ctx: &'ctx Context,
+        rc: &mut RenderContext<'reg, 'ctx>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        check_arity(h, "empty")?;
+        let comparison = match h.params()[0].value() {
+            Json::Null => true,
+            Json::String(s) => s.is_empty(),
+            Json::Array(a) => a.is_empty(),
+            Json::Object(o) => o.is_empty(),
+            Json::Bool(_) | Json::Number(_) => false,
+        };
+        render_condition(comparison, h, r, ctx, rc, out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::test::{render_fixture, test_error_against_configs};
+
+    #[test]
+    fn test_empty() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("empty", Box::new(EmptyHelper));
+
+        let templates = vec![
+            (r#"{{#empty Missing}}Foo{{/empty}}"#, "Foo"),
+            (r#"{{#empty ""}}Foo{{/empty}}"#, "Foo"),
+            (r#"{{#empty Memcache.Servers}}{{else}}Bar{{/empty}}"#, "Bar"),
+            (r#"{{#if (empty Region)}}{{else}}Bar{{/if}}"#, "Bar"),
+            (r#"{{#if (empty 0)}}{{else}}Bar{{/if}}"#, "Bar"),
+            (r#"{{empty Region.Missing}}"#, "true"),
+        ];
+
+        for (template, expected) in templates {
+            assert_eq!(render_fixture(&handlebars, template), expected);
+        }
+
+        test_error_against_configs(
+            &handlebars,
+            r#"{{#empty}}Foo{{/empty}}"#,
+            "'empty' requires exactly 1 parameter",
+        );
+    }
+}
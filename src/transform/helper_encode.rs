@@ -0,0 +1,108 @@
+use crate::transform::{string_param, EncodeFn};
+use handlebars::*;
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+
+/// Writes its param encoded or hashed, e.g. {{sha256 Secret}}. Registered once per encoding
+#[derive(Clone, Copy)]
+pub struct EncodeHelper {
+    pub name: &'static str,
+    pub encode: EncodeFn,
+}
+
+impl HelperDef for EncodeHelper {
+    fn call<'reg: 'rc, 'rc, 'ctx>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars,
+        _: &'ctx Context,
+        _: &mut RenderContext<'reg, 'ctx>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        if let Some(s) = string_param(h, 0, self.name)? {
+            out.write(&(self.encode)(&s))?;
+        }
+        Ok(())
+    }
+}
+
+/// Standard base64 with padding
+pub fn base64_encode(s: &str) -> String {
+    base64::encode(s)
+}
+
+/// The UTF-8 bytes as lowercase hex
+pub fn hex(s: &str) -> String {
+    s.bytes()
+        .fold(String::with_capacity(s.len() * 2), |mut hex, b| {
+            let _ = write!(hex, "{:02x}", b);
+            hex
+        })
+}
+
+pub fn md5(s: &str) -> String {
+    format!("{:x}", Md5::digest(s.as_bytes()))
+}
+
+pub fn sha1(s: &str) -> String {
+    format!("{:x}", Sha1::digest(s.as_bytes()))
+}
+
+pub fn sha256(s: &str) -> String {
+    format!("{:x}", Sha256::digest(s.as_bytes()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::test::test_against_configs;
+
+    #[test]
+    fn test_encode_helpers() {
+        let mut handlebars = Handlebars::new();
+        for (name, encode) in crate::transform::ENCODE_HELPERS {
+            handlebars.register_helper(
+                name,
+                Box::new(EncodeHelper {
+                    name,
+                    encode: *encode,
+                }),
+            );
+        }
+
+        let templates = vec![
+            ("{{base64-encode Region.Key}}", "VEVTVA=="),
+            ("{{base64-encode DB.Endpoint}}", "aG9zdC1uYW1lXFRFU1Qi"),
+            ("{{hex Region.Key}}", "54455354"),
+            ("{{hex DB.Endpoint}}", "686f73742d6e616d655c5445535422"),
+            (
+                "{{sha256 Region.Key}}",
+                "94ee059335e587e501cc4bf90613e0814f00a7b08bc7c648fd865a2af6a22cc2",
+            ),
+            (
+                "{{sha256 DB.Endpoint}}",
+                "46c5fb5e9473955b8eda3a254af1d91c9e343f4cf869027dc43b9f15fdce46d7",
+            ),
+            (
+                "{{sha256 ConfigEnabled}}",
+                "b5bea41b6c623f7c09f1bf24dcae58ebab3c0cdd90ad966bc43a45b44867e12b",
+            ),
+            (
+                "{{sha1 Region.Key}}",
+                "984816fd329622876e14907634264e6f332e9fb3",
+            ),
+            ("{{md5 Region.Key}}", "033bd94b1168d7e4f0d644c3c95e35bf"),
+            ("{{md5 DB.Endpoint}}", "ae152cc4eb5acb591f011f8c881b0f8c"),
+        ];
+
+        for (template, expected) in templates {
+            test_against_configs(&handlebars, template, expected)
+        }
+
+        assert!(handlebars
+            .render_template("{{sha256 Region}}", &serde_json::json!({"Region": {}}))
+            .is_err());
+    }
+}
@@ -0,0 +1,67 @@
+use crate::transform::{string_param, EscapeFn};
+use handlebars::*;
+
+/// Writes its param escaped for a format, e.g. {{xml-attr Name}}. Registered once per format
+#[derive(Clone, Copy)]
+pub struct EscapeHelper {
+    pub name: &'static str,
+    pub escape: EscapeFn,
+}
+
+impl HelperDef for EscapeHelper {
+    fn call<'reg: 'rc, 'rc, 'ctx>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars,
+        _: &'ctx Context,
+        _: &mut RenderContext<'reg, 'ctx>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        if let Some(s) = string_param(h, 0, self.name)? {
+            out.write(&(self.escape)(&s))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::test::test_against_configs;
+
+    #[test]
+    fn test_escape_helpers() {
+        let mut handlebars = Handlebars::new();
+        for (name, escape) in crate::transform::ESCAPE_HELPERS {
+            handlebars.register_helper(
+                name,
+                Box::new(EscapeHelper {
+                    name,
+                    escape: *escape,
+                }),
+            );
+        }
+
+        let templates = vec![
+            ("{{xml-attr DB.Endpoint}}", "host-name\\TEST&quot;"),
+            ("{{xml-text DB.Endpoint}}", "host-name\\TEST\""),
+            ("{{json-string DB.Endpoint}}", "host-name\\\\TEST\\\""),
+            ("{{properties-value DB.Endpoint}}", "host-name\\\\TEST\""),
+            ("{{shell-quote DB.Endpoint}}", "'host-name\\TEST\"'"),
+            ("{{shell-quote Region.Key}}", "TEST"),
+            (
+                "{{properties-value NonSlashService.endpoint}}",
+                "https\\://nonslash.com",
+            ),
+            ("{{json-string ConfigEnabled}}", "true"),
+        ];
+
+        for (template, expected) in templates {
+            test_against_configs(&handlebars, template, expected)
+        }
+
+        assert!(handlebars
+            .render_template("{{xml-attr Region}}", &serde_json::json!({"Region": {}}))
+            .is_err());
+    }
+}
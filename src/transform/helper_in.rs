@@ -0,0 +1,74 @@
+use crate::transform::{check_arity, render_condition};
+use handlebars::*;
+use serde_json::value::Value as Json;
+
+#[derive(Clone, Copy)]
+pub struct InHelper;
+
+impl HelperDef for InHelper {
+    // Whether the first param is an item of an array, or equal to any of the params after it
+    // Usage: {{#in Region.Key Regions}}...{{/in}} {{#in Region.Key "TEST" "TEST2"}}...{{/in}}
+    fn call<'reg: 'rc, 'rc, 'ctx>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        r: &'reg Handlebars,
+        ctx: &'ctx Context,
+        rc: &mut RenderContext<'reg, 'ctx>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        check_arity(h, "in")?;
+        let value = h.params()[0].value();
+        let candidates = &h.params()[1..];
+
+        let comparison = match candidates {
+            [list] => match list.value() {
+                Json::Array(items) => items.contains(value),
+                other => other == value,
+            },
+            _ => candidates.iter().any(|c| c.value() == value),
+        };
+        render_condition(comparison, h, r, ctx, rc, out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::helper_split::SplitHelper;
+    use crate::transform::test::{render_fixture, test_error_against_configs};
+
+    #[test]
+    fn test_in() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("in", Box::new(InHelper));
+        handlebars.register_helper("split", Box::new(SplitHelper));
+
+        let templates = vec![
+            (r#"{{#in Region.Key "UAT" "TEST"}}Foo{{/in}}"#, "Foo"),
+            (
+                r#"{{#in Region.Key "UAT" "PROD"}}{{else}}Bar{{/in}}"#,
+                "Bar",
+            ),
+            (
+                r#"{{#if (in Region.Key (split "UAT,TEST" ","))}}Foo{{/if}}"#,
+                "Foo",
+            ),
+            (
+                r#"{{#if (in Memcache.Servers.0 Memcache.Servers)}}Foo{{/if}}"#,
+                "Foo",
+            ),
+            (r#"{{#if (in Region.Key Missing)}}Foo{{/if}}"#, ""),
+            (r#"{{in Region.Key "TEST"}}"#, "true"),
+        ];
+
+        for (template, expected) in templates {
+            assert_eq!(render_fixture(&handlebars, template), expected);
+        }
+
+        test_error_against_configs(
+            &handlebars,
+            r#"{{#in Region.Key}}Foo{{/in}}"#,
+            "'in' requires at least 2 parameters",
+        );
+    }
+}
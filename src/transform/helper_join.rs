@@ -0,0 +1,140 @@
+use crate::transform::string_param;
+use handlebars::*;
+use serde_json::value::Value as Json;
+
+#[derive(Clone, Copy)]
+pub struct JoinHelper;
+
+impl HelperDef for JoinHelper {
+    // Joins the items of an array with a separator, defaulting to ",". As a block the template
+    // is rendered for each item, as comma-list does
+    // Usage: {{join Hosts ";"}} {{#join Servers " "}}{{Endpoint}}:{{Port}}{{/join}}
+    fn call<'reg: 'rc, 'rc, 'ctx>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        r: &'reg Handlebars,
+        ctx: &'ctx Context,
+        rc: &mut RenderContext<'reg, 'ctx>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let value = h
+            .param(0)
+            .ok_or_else(|| RenderError::new("Param not found for helper \"join\""))?;
+        let separator = match h.param(1) {
+            Some(_) => string_param(h, 1, "join")?.unwrap_or_default(),
+            None => ",".to_owned(),
+        };
+
+        match *value.value() {
+            Json::Array(ref list) => {
+                let items = match h.template() {
+                    Some(template) => render_items(h, r, ctx, rc, template, value, list)?,
+                    None => list
+                        .iter()
+                        .filter(|item| !item.is_null())
+                        .map(|item| match item {
+                            Json::String(s) => Ok(s.to_owned()),
+                            Json::Number(_) | Json::Bool(_) => Ok(item.to_string()),
+                            _ => Err(RenderError::new(format!(
+                                "Array item is not string for helper \"join\": {:?}",
+                                item
+                            ))),
+                        })
+                        .collect::<Result<Vec<_>, _>>()?,
+                };
+                out.write(&items.join(&separator))?;
+
+                Ok(())
+            }
+            Json::Null => Ok(()),
+            _ => Err(RenderError::new(format!(
+                "Param type is not array for helper \"join\": {:?}",
+                value
+            ))),
+        }
+    }
+}
+
+/// Renders the block once for each item of the array, with the item as the context and @index,
+/// @first and @last set as in #each
+pub(crate) fn render_items<'reg: 'rc, 'rc, 'ctx>(
+    h: &Helper<'reg, 'rc>,
+    r: &'reg Handlebars,
+    ctx: &'ctx Context,
+    rc: &mut RenderContext<'reg, 'ctx>,
+    template: &'reg Template,
+    value: &PathAndJson<'reg, 'rc>,
+    list: &[Json],
+) -> Result<Vec<String>, RenderError> {
+    let mut render_list = Vec::new();
+
+    for (i, item) in list.iter().enumerate() {
+        let set_locals = |block: &mut BlockContext| {
+            block.set_local_var("@index".to_string(), to_json(i));
+            block.set_local_var("@first".to_string(), to_json(i == 0));
+            block.set_local_var("@last".to_string(), to_json(i == list.len() - 1));
+        };
+
+        let mut local_rc = rc.clone();
+        let block_rc = local_rc.block_mut().unwrap();
+        if let Some(inner_path) = value.context_path() {
+            let block_path = block_rc.base_path_mut();
+            block_path.append(&mut inner_path.to_owned());
+            block_path.push(i.to_string());
+        }
+        set_locals(block_rc);
+
+        if let Some(block_param) = h.block_param() {
+            let mut new_block = BlockContext::new();
+            set_locals(&mut new_block);
+            let mut block_params = BlockParams::new();
+            block_params.add_value(block_param, to_json(item))?;
+            new_block.set_block_params(block_params);
+            local_rc.push_block(new_block);
+
+            render_list.push(template.renders(r, ctx, &mut local_rc)?);
+        } else {
+            render_list.push(template.renders(r, ctx, &mut local_rc)?);
+        }
+    }
+
+    Ok(render_list)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::test::test_against_configs;
+
+    #[test]
+    fn test_join() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("join", Box::new(JoinHelper));
+
+        let templates = vec![
+            (
+                "{{#join Memcache.Servers \" \"}}{{Endpoint}}:{{Port}}{{/join}}",
+                "192.168.1.100:1122 192.168.1.101:1122 192.168.1.102:1122",
+            ),
+            ("{{#join Memcache.Servers}}{{@index}}{{/join}}", "0,1,2"),
+            (
+                "{{#join Memcache.Servers \";\" as |server|}}{{server.Endpoint}}{{/join}}",
+                "192.168.1.100;192.168.1.101;192.168.1.102",
+            ),
+        ];
+        for (template, expected) in templates {
+            test_against_configs(&handlebars, template, expected)
+        }
+
+        let data = serde_json::json!({"Hosts": ["a", null, 2, true], "Nested": [{}]});
+        assert_eq!(
+            handlebars
+                .render_template("{{join Hosts \", \"}}", &data)
+                .unwrap(),
+            "a, 2, true"
+        );
+        assert!(handlebars
+            .render_template("{{join Nested}}", &data)
+            .is_err());
+    }
+}
@@ -0,0 +1,74 @@
+use crate::transform::{check_arity, render_condition, string_param};
+use handlebars::*;
+use regex::Regex;
+
+#[derive(Clone, Copy)]
+pub struct MatchesHelper;
+
+impl HelperDef for MatchesHelper {
+    // Whether a value matches a regex anywhere, so anchor it with ^ and $ to match the whole value.
+    // Backslashes in the pattern need doubling, as handlebars string literals are JSON strings
+    // Usage: {{#matches Region.Key "^(TEST|UAT)\\d*$"}}...{{/matches}}
+    fn call<'reg: 'rc, 'rc, 'ctx>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        r: &'reg Handlebars,
+        ctx: &'ctx Context,
+        rc: &mut RenderContext<'reg, 'ctx>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        check_arity(h, "matches")?;
+        let value = string_param(h, 0, "matches")?;
+        let pattern = string_param(h, 1, "matches")?.unwrap_or_default();
+        let regex = Regex::new(&pattern).map_err(|e| {
+            RenderError::new(format!("Invalid regex for helper \"matches\": {}", e))
+        })?;
+
+        let comparison = value.is_some_and(|value| regex.is_match(&value));
+        render_condition(comparison, h, r, ctx, rc, out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::test::{render_fixture, test_error_against_configs};
+
+    #[test]
+    fn test_matches() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("matches", Box::new(MatchesHelper));
+
+        let templates = vec![
+            (
+                r#"{{#matches Region.Key "^TEST\\d*$"}}Foo{{/matches}}"#,
+                "Foo",
+            ),
+            (
+                r#"{{#matches Region.Key "^PROD"}}{{else}}Bar{{/matches}}"#,
+                "Bar",
+            ),
+            (
+                r#"{{#if (matches Memcache.Servers.0.Endpoint "^192\\.168\\.")}}Foo{{/if}}"#,
+                "Foo",
+            ),
+            (r#"{{#if (matches Missing ".*")}}Foo{{/if}}"#, ""),
+            (r#"{{matches SlashService.endpoint "/$"}}"#, "true"),
+        ];
+
+        for (template, expected) in templates {
+            assert_eq!(render_fixture(&handlebars, template), expected);
+        }
+
+        test_error_against_configs(
+            &handlebars,
+            r#"{{#matches Region.Key}}Foo{{/matches}}"#,
+            "'matches' requires exactly 2 parameters",
+        );
+        assert!(handlebars
+            .render_template(r#"{{matches Region.Key "("}}"#, &serde_json::Value::Null)
+            .unwrap_err()
+            .to_string()
+            .contains("Invalid regex for helper \"matches\""));
+    }
+}
@@ -0,0 +1,54 @@
+use crate::transform::{check_arity, is_truthy, render_condition};
+use handlebars::*;
+
+#[derive(Clone, Copy)]
+pub struct NotHelper;
+
+impl HelperDef for NotHelper {
+    // Logical NOT of a param
+    // Usage: {{#not ConfigEnabled}}...{{/not}} {{#if (not (eq Region.Key "TEST"))}}...{{/if}}
+    fn call<'reg: 'rc, 'rc, 'ctx>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        r: &'reg Handlebars,
+        ctx: &'ctx Context,
+        rc: &mut RenderContext<'reg, 'ctx>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        check_arity(h, "not")?;
+        let comparison = !is_truthy(h.params()[0].value());
+        render_condition(comparison, h, r, ctx, rc, out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::helper_equal::EqualHelper;
+    use crate::transform::test::{render_fixture, test_error_against_configs};
+
+    #[test]
+    fn test_not() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("eq", Box::new(EqualHelper));
+        handlebars.register_helper("not", Box::new(NotHelper));
+
+        let templates = vec![
+            (r#"{{#not (eq Region.Key "NO")}}Foo{{/not}}"#, "Foo"),
+            (r#"{{#not ConfigEnabled}}Foo{{else}}Bar{{/not}}"#, "Bar"),
+            (r#"{{#if (not Missing)}}Foo{{/if}}"#, "Foo"),
+            (r#"{{#if (not (not Region.Key))}}Foo{{/if}}"#, "Foo"),
+            (r#"{{not Memcache.Servers}}"#, ""),
+        ];
+
+        for (template, expected) in templates {
+            assert_eq!(render_fixture(&handlebars, template), expected);
+        }
+
+        test_error_against_configs(
+            &handlebars,
+            r#"{{#not ConfigEnabled Region.Key}}Foo{{/not}}"#,
+            "'not' requires exactly 1 parameter",
+        );
+    }
+}
@@ -0,0 +1,52 @@
+use crate::transform::{check_arity, render_condition};
+use handlebars::*;
+
+#[derive(Clone, Copy)]
+pub struct NotEqualHelper;
+
+impl HelperDef for NotEqualHelper {
+    // The opposite of equal
+    // Usage: {{#ne Region.Key "PROD"}}...{{/ne}}
+    fn call<'reg: 'rc, 'rc, 'ctx>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        r: &'reg Handlebars,
+        ctx: &'ctx Context,
+        rc: &mut RenderContext<'reg, 'ctx>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        check_arity(h, "ne")?;
+        let comparison = h.params()[0].value() != h.params()[1].value();
+        render_condition(comparison, h, r, ctx, rc, out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::test::{render_fixture, test_error_against_configs};
+
+    #[test]
+    fn test_not_equal() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("ne", Box::new(NotEqualHelper));
+
+        let templates = vec![
+            (r#"{{#ne Region.Key "PROD"}}Foo{{/ne}}"#, "Foo"),
+            (r#"{{#ne Region.Key "TEST"}}{{else}}Bar{{/ne}}"#, "Bar"),
+            (r#"{{#if (ne Region.Key null)}}Foo{{/if}}"#, "Foo"),
+            (r#"{{#if (ne ConfigEnabled "true")}}Foo{{/if}}"#, "Foo"),
+            (r#"{{ne Region.Key "TEST"}}"#, ""),
+        ];
+
+        for (template, expected) in templates {
+            assert_eq!(render_fixture(&handlebars, template), expected);
+        }
+
+        test_error_against_configs(
+            &handlebars,
+            r#"{{#ne Region.Key}}Foo{{/ne}}"#,
+            "'ne' requires exactly 2 parameters",
+        );
+    }
+}
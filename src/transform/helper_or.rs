@@ -19,7 +19,7 @@ impl HelperDef for OrHelper {
         let comparison = h
             .params()
             .iter()
-            .any(|p| p.value().as_str().map_or(false, |v| !v.is_empty()));
+            .any(|p| p.value().as_str().is_some_and(|v| !v.is_empty()));
 
         if h.is_block() {
             let template = if comparison {
@@ -29,7 +29,7 @@ impl HelperDef for OrHelper {
             };
 
             match template {
-                Some(ref t) => t.render(r, ctx, rc, out),
+                Some(t) => t.render(r, ctx, rc, out),
                 None => Ok(()),
             }
         } else {
@@ -0,0 +1,91 @@
+use crate::transform::{integer_param, string_param};
+use handlebars::*;
+
+#[derive(Clone, Copy)]
+pub struct PadHelper;
+
+impl HelperDef for PadHelper {
+    // Pads the start of a string to a width, with spaces or the given character. right=true pads
+    // the end instead. Strings already at least as wide are left as they are
+    // Usage: {{pad Id 6 "0"}} {{pad Name 10 right=true}}
+    fn call<'reg: 'rc, 'rc, 'ctx>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars,
+        _: &'ctx Context,
+        _: &mut RenderContext<'reg, 'ctx>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let value = string_param(h, 0, "pad")?;
+        let width = integer_param(h, 1, "pad")?
+            .ok_or_else(|| RenderError::new("Param not found for helper \"pad\""))?;
+        let fill = match h.param(2) {
+            Some(_) => string_param(h, 2, "pad")?.unwrap_or_default(),
+            None => " ".to_owned(),
+        };
+        let mut fill_chars = fill.chars();
+        let fill = match (fill_chars.next(), fill_chars.next()) {
+            (Some(c), None) => c,
+            _ => {
+                return Err(RenderError::new(format!(
+                    "Param 2 must be a single character for helper \"pad\": {:?}",
+                    fill
+                )))
+            }
+        };
+        let right = h
+            .hash_get("right")
+            .is_some_and(|r| r.value().as_bool() == Some(true));
+
+        if let Some(value) = value {
+            let len = value.chars().count();
+            let padding = (len..width.max(0) as usize)
+                .map(|_| fill)
+                .collect::<String>();
+            if right {
+                out.write(&value)?;
+                out.write(&padding)?;
+            } else {
+                out.write(&padding)?;
+                out.write(&value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::test::{render_fixture, test_against_configs};
+
+    #[test]
+    fn test_pad() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("pad", Box::new(PadHelper));
+
+        let templates = vec![
+            ("{{pad Memcache.Servers.0.Port 6 \"0\"}}", "001122"),
+            ("{{pad UpperCaseString 3}}", "UPPERCASE"),
+        ];
+        for (template, expected) in templates {
+            test_against_configs(&handlebars, template, expected)
+        }
+        assert_eq!(
+            render_fixture(&handlebars, "[{{pad Region.Key 6}}]"),
+            "[  TEST]"
+        );
+        assert_eq!(
+            render_fixture(&handlebars, "[{{pad Region.Key 6 right=true}}]"),
+            "[TEST  ]"
+        );
+        assert_eq!(
+            render_fixture(&handlebars, "{{pad (pad Region.Key 5 \"-\") 6 \"+\"}}"),
+            "+-TEST"
+        );
+
+        assert!(handlebars
+            .render_template("{{pad Key 3 \"ab\"}}", &serde_json::json!({"Key": "a"}))
+            .is_err());
+    }
+}
@@ -0,0 +1,68 @@
+use crate::transform::string_param;
+use handlebars::*;
+use regex::Regex;
+
+#[derive(Clone, Copy)]
+pub struct RegexReplaceHelper;
+
+impl HelperDef for RegexReplaceHelper {
+    // Replaces every match of a regex. The replacement can refer to capture groups as $1 or ${name}.
+    // Backslashes in the pattern need doubling, as handlebars string literals are JSON strings
+    // Usage: {{regex-replace Url "^https?://([^/]+).*$" "$1"}}
+    fn call<'reg: 'rc, 'rc, 'ctx>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars,
+        _: &'ctx Context,
+        _: &mut RenderContext<'reg, 'ctx>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let value = string_param(h, 0, "regex-replace")?;
+        let pattern = string_param(h, 1, "regex-replace")?.unwrap_or_default();
+        let replacement = string_param(h, 2, "regex-replace")?.unwrap_or_default();
+        let regex = Regex::new(&pattern).map_err(|e| {
+            RenderError::new(format!("Invalid regex for helper \"regex-replace\": {}", e))
+        })?;
+
+        if let Some(value) = value {
+            out.write(&regex.replace_all(&value, replacement.as_str()))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::test::{test_against_configs, test_error_against_configs};
+
+    #[test]
+    fn test_regex_replace() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("regex-replace", Box::new(RegexReplaceHelper));
+
+        let templates = vec![
+            (
+                r#"{{regex-replace PathService.endpoint "^https?://([^/]+).*$" "$1"}}"#,
+                "path.com",
+            ),
+            (
+                r#"{{regex-replace Memcache.Servers.1.Endpoint "\\.(?P<last>\\d+)$" ".x${last}"}}"#,
+                "192.168.1.x101",
+            ),
+            (
+                r#"{{regex-replace (regex-replace Region.Key "T" "t") "^t" "T"}}"#,
+                "TESt",
+            ),
+        ];
+        for (template, expected) in templates {
+            test_against_configs(&handlebars, template, expected)
+        }
+
+        test_error_against_configs(
+            &handlebars,
+            r#"{{regex-replace Region.Key "(" ""}}"#,
+            "Invalid regex for helper \"regex-replace\": regex parse error:\n    (\n    ^\nerror: unclosed group",
+        );
+    }
+}
@@ -0,0 +1,63 @@
+use crate::transform::string_param;
+use handlebars::*;
+
+#[derive(Clone, Copy)]
+pub struct ReplaceHelper;
+
+impl HelperDef for ReplaceHelper {
+    // Replaces every occurrence of a string
+    // Usage: {{replace Host "." "-"}}
+    fn call<'reg: 'rc, 'rc, 'ctx>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars,
+        _: &'ctx Context,
+        _: &mut RenderContext<'reg, 'ctx>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let value = string_param(h, 0, "replace")?;
+        let from = string_param(h, 1, "replace")?.unwrap_or_default();
+        let to = string_param(h, 2, "replace")?.unwrap_or_default();
+        if from.is_empty() {
+            return Err(RenderError::new(
+                "Param 1 can not be empty for helper \"replace\"",
+            ));
+        }
+
+        if let Some(value) = value {
+            out.write(&value.replace(&from, &to))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::helper_uppercase::UppercaseHelper;
+    use crate::transform::test::test_against_configs;
+
+    #[test]
+    fn test_replace() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("replace", Box::new(ReplaceHelper));
+        handlebars.register_helper("uppercase", Box::new(UppercaseHelper));
+
+        test_against_configs(
+            &handlebars,
+            "{{replace Memcache.Servers.0.Endpoint \".\" \"-\"}}",
+            "192-168-1-100",
+        );
+        test_against_configs(
+            &handlebars,
+            "{{uppercase (replace NonSlashService.notAnEndpoint \".no-slash\" \"\")}}",
+            "NO-PROTOCOL.COM",
+        );
+        assert!(handlebars
+            .render_template(
+                "{{replace Key \"\" \"-\"}}",
+                &serde_json::json!({"Key": "a"})
+            )
+            .is_err());
+    }
+}
@@ -0,0 +1,69 @@
+use crate::transform::string_param;
+use handlebars::*;
+use serde_json::value::Value as Json;
+
+#[derive(Clone, Copy)]
+pub struct SplitHelper;
+
+impl HelperDef for SplitHelper {
+    // Splits a string into an array on a separator, for use as a subexpression
+    // Usage: {{#each (split Hosts ",")}}{{this}}{{/each}} {{lookup (split Name "-") 0}}
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<Option<ScopedJson<'reg, 'rc>>, RenderError> {
+        let value = string_param(h, 0, "split")?;
+        let separator = string_param(h, 1, "split")?.unwrap_or_default();
+        if separator.is_empty() {
+            return Err(RenderError::new(
+                "Param 1 can not be empty for helper \"split\"",
+            ));
+        }
+
+        let split = match value {
+            Some(value) => Json::Array(
+                value
+                    .split(separator.as_str())
+                    .map(|s| Json::String(s.to_owned()))
+                    .collect(),
+            ),
+            None => Json::Null,
+        };
+        Ok(Some(ScopedJson::Derived(split)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::helper_join::JoinHelper;
+    use crate::transform::test::test_against_configs;
+
+    #[test]
+    fn test_split() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("split", Box::new(SplitHelper));
+        handlebars.register_helper("join", Box::new(JoinHelper));
+
+        let templates = vec![
+            (
+                "{{#each (split Memcache.Servers.0.Endpoint \".\")}}[{{this}}]{{/each}}",
+                "[192][168][1][100]",
+            ),
+            (
+                "{{lookup (split PathService.endpoint \"/\") 2}}",
+                "path.com",
+            ),
+            (
+                "{{join (split NonSlashService.notAnEndpoint \".\") \"_\"}}",
+                "no-protocol_no-slash_com",
+            ),
+        ];
+        for (template, expected) in templates {
+            test_against_configs(&handlebars, template, expected)
+        }
+    }
+}
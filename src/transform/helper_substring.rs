@@ -0,0 +1,68 @@
+use crate::transform::{integer_param, string_param};
+use handlebars::*;
+
+#[derive(Clone, Copy)]
+pub struct SubstringHelper;
+
+impl HelperDef for SubstringHelper {
+    // Takes the characters from start up to, but not including, end. Negative positions count
+    // back from the end of the string, and positions past either end are clamped
+    // Usage: {{substring Sha 0 7}} {{substring Name -3}}
+    fn call<'reg: 'rc, 'rc, 'ctx>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars,
+        _: &'ctx Context,
+        _: &mut RenderContext<'reg, 'ctx>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let value = string_param(h, 0, "substring")?;
+        let start = integer_param(h, 1, "substring")?
+            .ok_or_else(|| RenderError::new("Param not found for helper \"substring\""))?;
+        let end = integer_param(h, 2, "substring")?;
+
+        if let Some(value) = value {
+            let chars = value.chars().collect::<Vec<_>>();
+            let position = |index: i64| {
+                let len = chars.len() as i64;
+                (if index < 0 { len + index } else { index }).clamp(0, len) as usize
+            };
+            let start = position(start);
+            let end = end.map(position).unwrap_or(chars.len());
+
+            if start < end {
+                out.write(&chars[start..end].iter().collect::<String>())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::test::test_against_configs;
+
+    #[test]
+    fn test_substring() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("substring", Box::new(SubstringHelper));
+
+        let templates = vec![
+            ("{{substring UpperCaseString 0 5}}", "UPPER"),
+            ("{{substring UpperCaseString 5}}", "CASE"),
+            ("{{substring UpperCaseString -4}}", "CASE"),
+            ("{{substring UpperCaseString 2 -4}}", "PER"),
+            ("{{substring UpperCaseString 4 100}}", "RCASE"),
+            ("{{substring UpperCaseString 6 2}}", ""),
+            ("{{substring (substring UpperCaseString 1) 0 2}}", "PP"),
+        ];
+        for (template, expected) in templates {
+            test_against_configs(&handlebars, template, expected)
+        }
+
+        assert!(handlebars
+            .render_template("{{substring Key \"1\"}}", &serde_json::json!({"Key": "a"}))
+            .is_err());
+    }
+}
@@ -0,0 +1,77 @@
+use crate::transform::{offset_lines, usize_hash};
+use handlebars::*;
+use serde::Serialize;
+use serde_json::ser::{PrettyFormatter, Serializer};
+use serde_json::value::Value as Json;
+
+#[derive(Clone, Copy)]
+pub struct ToJsonHelper;
+
+impl HelperDef for ToJsonHelper {
+    // Serializes the value as compact JSON, or pretty printed with indent=N spaces per level.
+    // offset=N indents every line after the first to line up with the template
+    fn call<'reg: 'rc, 'rc, 'ctx>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars,
+        _: &'ctx Context,
+        _: &mut RenderContext<'reg, 'ctx>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let value = h
+            .param(0)
+            .ok_or_else(|| RenderError::new("Param not found for helper \"to-json\""))?;
+        let indent = usize_hash(h, "to-json", "indent")?;
+        let offset = usize_hash(h, "to-json", "offset")?.unwrap_or(0);
+
+        let json = match (value.value(), indent) {
+            (Json::Null, _) => return Ok(()),
+            (value, None) => serde_json::to_string(value),
+            (value, Some(indent)) => {
+                let indent = " ".repeat(indent);
+                let mut buf = Vec::new();
+                let mut serializer = Serializer::with_formatter(
+                    &mut buf,
+                    PrettyFormatter::with_indent(indent.as_bytes()),
+                );
+                value
+                    .serialize(&mut serializer)
+                    .map(|_| String::from_utf8_lossy(&buf).into_owned())
+            }
+        }
+        .map_err(|e| RenderError::from_error("Error serializing for helper \"to-json\"", e))?;
+
+        out.write(&offset_lines(&json, offset))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::test::test_against_configs;
+
+    #[test]
+    fn test_to_json() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("to-json", Box::new(ToJsonHelper));
+
+        let templates = vec![
+            ("{{to-json Region}}", r#"{"Key":"TEST"}"#),
+            ("{{to-json DB.Endpoint}}", r#""host-name\\TEST\"""#),
+            ("{{to-json ConfigEnabled}}", "true"),
+            (
+                "{{to-json DB indent=2}}",
+                "{\n  \"Endpoint\": \"host-name\\\\TEST\\\"\"\n}",
+            ),
+            (
+                "{{to-json Region indent=4 offset=2}}",
+                "{\n      \"Key\": \"TEST\"\n  }",
+            ),
+        ];
+
+        for (template, expected) in templates {
+            test_against_configs(&handlebars, template, expected)
+        }
+    }
+}
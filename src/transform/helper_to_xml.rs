@@ -0,0 +1,197 @@
+use crate::transform::escape;
+use crate::transform::{offset_lines, usize_hash};
+use handlebars::*;
+use serde_json::value::Value as Json;
+use serde_json::Map;
+
+#[derive(Clone, Copy)]
+pub struct ToXmlHelper;
+
+impl HelperDef for ToXmlHelper {
+    // Serializes the value as XML elements named after its keys, with arrays repeating the
+    // element for each item. root="Name" wraps the value in an element, and is required for
+    // arrays. The elements are written on one line unless indent=N spaces per level is given.
+    // offset=N indents every line after the first to line up with the template
+    fn call<'reg: 'rc, 'rc, 'ctx>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars,
+        _: &'ctx Context,
+        _: &mut RenderContext<'reg, 'ctx>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let value = h
+            .param(0)
+            .ok_or_else(|| RenderError::new("Param not found for helper \"to-xml\""))?;
+        let root = match h.hash_get("root").map(|r| r.value()) {
+            None => None,
+            Some(Json::String(root)) => Some(root.as_str()),
+            Some(root) => {
+                return Err(RenderError::new(format!(
+                    "Param \"root\" is not a string for helper \"to-xml\": {:?}",
+                    root
+                )))
+            }
+        };
+        let mut writer = XmlWriter {
+            indent: usize_hash(h, "to-xml", "indent")?,
+            xml: String::new(),
+        };
+        let offset = usize_hash(h, "to-xml", "offset")?.unwrap_or(0);
+
+        match (value.value(), root) {
+            (Json::Null, _) => return Ok(()),
+            (value, Some(root)) => writer.element(root, value, 0)?,
+            (Json::Object(map), None) => writer.children(map, 0)?,
+            (Json::Array(_), None) => {
+                return Err(RenderError::new(
+                    "Param \"root\" is required to write an array for helper \"to-xml\"",
+                ))
+            }
+            (value, None) => writer.xml.push_str(&escape_text(value)),
+        }
+
+        out.write(&offset_lines(&writer.xml, offset))?;
+        Ok(())
+    }
+}
+
+struct XmlWriter {
+    /// Spaces per nesting level, or None to write everything on one line
+    indent: Option<usize>,
+    xml: String,
+}
+
+impl XmlWriter {
+    fn line(&mut self, depth: usize, text: &str) {
+        if let Some(indent) = self.indent {
+            if !self.xml.is_empty() {
+                self.xml.push('\n');
+            }
+            self.xml.push_str(&" ".repeat(indent * depth));
+        }
+        self.xml.push_str(text);
+    }
+
+    fn element(&mut self, name: &str, value: &Json, depth: usize) -> Result<(), RenderError> {
+        if !is_xml_name(name) {
+            return Err(RenderError::new(format!(
+                "Key \"{}\" is not a valid element name for helper \"to-xml\"",
+                name
+            )));
+        }
+
+        match value {
+            Json::Array(items) => {
+                for item in items {
+                    self.element(name, item, depth)?;
+                }
+            }
+            Json::Object(map) if !map.is_empty() => {
+                self.line(depth, &format!("<{}>", name));
+                self.children(map, depth + 1)?;
+                self.line(depth, &format!("</{}>", name));
+            }
+            Json::Object(_) | Json::Null => self.line(depth, &format!("<{} />", name)),
+            value => self.line(depth, &format!("<{0}>{1}</{0}>", name, escape_text(value))),
+        }
+        Ok(())
+    }
+
+    fn children(&mut self, map: &Map<String, Json>, depth: usize) -> Result<(), RenderError> {
+        for (key, value) in map {
+            self.element(key, value, depth)?;
+        }
+        Ok(())
+    }
+}
+
+/// Element names start with a letter or underscore, followed by letters, digits, -, _ or .
+fn is_xml_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+fn escape_text(value: &Json) -> String {
+    match value {
+        Json::String(s) => escape::xml_text(s),
+        value => escape::xml_text(&value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::test::{test_against_configs, test_error_against_configs};
+
+    #[test]
+    fn test_to_xml() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("to-xml", Box::new(ToXmlHelper));
+
+        let templates = vec![
+            ("{{to-xml Region}}", "<Key>TEST</Key>"),
+            ("{{to-xml DB.Endpoint}}", "host-name\\TEST\""),
+            (
+                "{{to-xml PathService root=\"Path\"}}",
+                "<Path><endpoint>https://path.com/path/remove-this</endpoint>\
+                 <trailingSlash>https://trailing-path.com/path/should-still-remove/</trailingSlash></Path>",
+            ),
+            (
+                "{{to-xml Memcache indent=2 offset=4}}",
+                "<Servers>\n      <Endpoint>192.168.1.100</Endpoint>\n      <Port>1122</Port>\n    </Servers>\n    \
+                 <Servers>\n      <Endpoint>192.168.1.101</Endpoint>\n      <Port>1122</Port>\n    </Servers>\n    \
+                 <Servers>\n      <Endpoint>192.168.1.102</Endpoint>\n      <Port>1122</Port>\n    </Servers>",
+            ),
+            (
+                "{{to-xml Memcache.Servers root=\"Server\"}}",
+                "<Server><Endpoint>192.168.1.100</Endpoint><Port>1122</Port></Server>\
+                 <Server><Endpoint>192.168.1.101</Endpoint><Port>1122</Port></Server>\
+                 <Server><Endpoint>192.168.1.102</Endpoint><Port>1122</Port></Server>",
+            ),
+        ];
+
+        for (template, expected) in templates {
+            test_against_configs(&handlebars, template, expected)
+        }
+    }
+
+    #[test]
+    fn test_to_xml_escaping() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("to-xml", Box::new(ToXmlHelper));
+
+        let data = serde_json::json!({
+            "Value": {"Text": "<a & b>", "Empty": {}, "Missing": null, "Count": 3}
+        });
+        assert_eq!(
+            handlebars
+                .render_template("{{to-xml Value}}", &data)
+                .unwrap(),
+            "<Count>3</Count><Empty /><Missing /><Text>&lt;a &amp; b&gt;</Text>"
+        );
+
+        let invalid = serde_json::json!({"Value": {"not valid": 1}});
+        assert!(handlebars
+            .render_template("{{to-xml Value}}", &invalid)
+            .is_err());
+    }
+
+    #[test]
+    fn test_to_xml_errors() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("to-xml", Box::new(ToXmlHelper));
+
+        test_error_against_configs(
+            &handlebars,
+            "{{to-xml Memcache.Servers}}",
+            "Param \"root\" is required to write an array for helper \"to-xml\"",
+        );
+        test_error_against_configs(
+            &handlebars,
+            "{{to-xml Region root=\"1st\"}}",
+            "Key \"1st\" is not a valid element name for helper \"to-xml\"",
+        );
+    }
+}
@@ -0,0 +1,212 @@
+use crate::transform::{offset_lines, usize_hash};
+use handlebars::*;
+use serde_json::value::Value as Json;
+
+#[derive(Clone, Copy)]
+pub struct ToYamlHelper;
+
+impl HelperDef for ToYamlHelper {
+    // Serializes the value as a block style YAML document with indent=N spaces per level,
+    // defaulting to 2. offset=N indents every line after the first to line up with the template
+    fn call<'reg: 'rc, 'rc, 'ctx>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars,
+        _: &'ctx Context,
+        _: &mut RenderContext<'reg, 'ctx>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let value = h
+            .param(0)
+            .ok_or_else(|| RenderError::new("Param not found for helper \"to-yaml\""))?;
+        let indent = usize_hash(h, "to-yaml", "indent")?.unwrap_or(2);
+        let offset = usize_hash(h, "to-yaml", "offset")?.unwrap_or(0);
+        if indent < 2 {
+            return Err(RenderError::new(
+                "Param \"indent\" must be at least 2 for helper \"to-yaml\"",
+            ));
+        }
+
+        if !value.value().is_null() {
+            let yaml = block_lines(value.value(), indent).join("\n");
+            out.write(&offset_lines(&yaml, offset))?;
+        }
+        Ok(())
+    }
+}
+
+/// Lines of the value in block style, starting at column 0
+fn block_lines(value: &Json, indent: usize) -> Vec<String> {
+    let padding = " ".repeat(indent);
+    let mut lines = Vec::new();
+
+    match value {
+        Json::Object(map) if !map.is_empty() => {
+            for (key, value) in map {
+                if is_block(value) {
+                    lines.push(format!("{}:", scalar(key)));
+                    lines.extend(
+                        block_lines(value, indent)
+                            .into_iter()
+                            .map(|line| format!("{}{}", padding, line)),
+                    );
+                } else {
+                    lines.push(format!("{}: {}", scalar(key), flow(value)));
+                }
+            }
+        }
+        Json::Array(items) if !items.is_empty() => {
+            let dash = format!("{:<width$}", "-", width = indent);
+            for item in items {
+                if is_block(item) {
+                    for (index, line) in block_lines(item, indent).into_iter().enumerate() {
+                        let prefix = if index == 0 { &dash } else { &padding };
+                        lines.push(format!("{}{}", prefix, line));
+                    }
+                } else {
+                    lines.push(format!("{}{}", dash, flow(item)));
+                }
+            }
+        }
+        value => lines.push(flow(value)),
+    }
+
+    lines
+}
+
+/// Non empty objects and arrays are written over several lines
+fn is_block(value: &Json) -> bool {
+    match value {
+        Json::Object(map) => !map.is_empty(),
+        Json::Array(items) => !items.is_empty(),
+        _ => false,
+    }
+}
+
+/// A value which fits on one line
+fn flow(value: &Json) -> String {
+    match value {
+        Json::Null => "null".to_owned(),
+        Json::String(s) => scalar(s),
+        Json::Object(_) => "{}".to_owned(),
+        Json::Array(_) => "[]".to_owned(),
+        value => value.to_string(),
+    }
+}
+
+/// Writes the string unquoted when YAML would read it back as the same string, otherwise double
+/// quoted. JSON string escaping is valid in YAML double quoted scalars
+fn scalar(s: &str) -> String {
+    const INDICATORS: &str = "-?:,[]{}#&*!|>'\"%@`";
+    const RESERVED: [&str; 11] = [
+        "true", "false", "yes", "no", "on", "off", "y", "n", "null", "~", "<<",
+    ];
+
+    let plain = match s.chars().next() {
+        None => false,
+        Some(first) => {
+            !INDICATORS.contains(first)
+                && !first.is_ascii_digit()
+                && !matches!(first, '.' | '+' | ' ')
+                && !s.ends_with([' ', ':'])
+                && !s.contains(": ")
+                && !s.contains(" #")
+                && !s.chars().any(char::is_control)
+                && !RESERVED.contains(&s.to_lowercase().as_str())
+        }
+    };
+
+    if plain {
+        s.to_owned()
+    } else {
+        serde_json::to_string(s).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::test::test_against_configs;
+    use yaml_rust::{Yaml, YamlLoader};
+
+    #[test]
+    fn test_to_yaml() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("to-yaml", Box::new(ToYamlHelper));
+
+        let templates = vec![
+            ("{{to-yaml Region}}", "Key: TEST"),
+            ("{{to-yaml DB}}", "Endpoint: host-name\\TEST\""),
+            (
+                "{{to-yaml Memcache}}",
+                "Servers:\n  \
+                 - Endpoint: \"192.168.1.100\"\n    Port: \"1122\"\n  \
+                 - Endpoint: \"192.168.1.101\"\n    Port: \"1122\"\n  \
+                 - Endpoint: \"192.168.1.102\"\n    Port: \"1122\"",
+            ),
+            (
+                "{{to-yaml SlashService indent=4 offset=2}}",
+                "endpoint: https://slash.com/",
+            ),
+            (
+                "{{to-yaml Memcache.Servers indent=4 offset=2}}",
+                "-   Endpoint: \"192.168.1.100\"\n      Port: \"1122\"\n  \
+                 -   Endpoint: \"192.168.1.101\"\n      Port: \"1122\"\n  \
+                 -   Endpoint: \"192.168.1.102\"\n      Port: \"1122\"",
+            ),
+        ];
+
+        for (template, expected) in templates {
+            test_against_configs(&handlebars, template, expected)
+        }
+    }
+
+    #[test]
+    fn test_to_yaml_round_trip() {
+        let value = serde_json::json!({
+            "plain": "value with spaces",
+            "numeric": "1122",
+            "boolean": "true",
+            "empty": "",
+            "quoted": "'single' and \"double\"",
+            "special": "- starts: with # indicators",
+            "multiline": "line 1\nline 2\ttabbed",
+            "unicode": "caf\u{e9} \u{1f600}",
+            "nested": {"list": [1, 2.5, null, [true, "x"], {}, []], "empty": {}},
+        });
+        let yaml = block_lines(&value, 2).join("\n");
+
+        let parsed = YamlLoader::load_from_str(&yaml).unwrap().remove(0);
+        let nested = &parsed["nested"];
+        assert_eq!(parsed["plain"].as_str(), Some("value with spaces"));
+        assert_eq!(parsed["numeric"].as_str(), Some("1122"));
+        assert_eq!(parsed["boolean"].as_str(), Some("true"));
+        assert_eq!(parsed["empty"].as_str(), Some(""));
+        assert_eq!(parsed["quoted"].as_str(), Some("'single' and \"double\""));
+        assert_eq!(
+            parsed["special"].as_str(),
+            Some("- starts: with # indicators")
+        );
+        assert_eq!(parsed["multiline"].as_str(), Some("line 1\nline 2\ttabbed"));
+        assert_eq!(parsed["unicode"].as_str(), Some("caf\u{e9} \u{1f600}"));
+        assert_eq!(nested["list"][0].as_i64(), Some(1));
+        assert_eq!(nested["list"][1].as_f64(), Some(2.5));
+        assert!(nested["list"][2].is_null());
+        assert_eq!(nested["list"][3][1].as_str(), Some("x"));
+        assert_eq!(nested["list"][4], Yaml::Hash(Default::default()));
+        assert_eq!(nested["list"][5], Yaml::Array(vec![]));
+    }
+
+    #[test]
+    fn test_to_yaml_indent() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("to-yaml", Box::new(ToYamlHelper));
+
+        assert!(handlebars
+            .render_template("{{to-yaml Region indent=1}}", &serde_json::json!({}))
+            .is_err());
+        assert!(handlebars
+            .render_template("{{to-yaml Region indent=\"2\"}}", &serde_json::json!({}))
+            .is_err());
+    }
+}
@@ -0,0 +1,45 @@
+use crate::transform::string_param;
+use handlebars::*;
+
+#[derive(Clone, Copy)]
+pub struct TrimHelper;
+
+impl HelperDef for TrimHelper {
+    // Removes leading and trailing whitespace
+    fn call<'reg: 'rc, 'rc, 'ctx>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars,
+        _: &'ctx Context,
+        _: &mut RenderContext<'reg, 'ctx>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        if let Some(s) = string_param(h, 0, "trim")? {
+            out.write(s.trim())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::helper_concat::ConcatHelper;
+    use crate::transform::test::{render_fixture, test_against_configs};
+
+    #[test]
+    fn test_trim() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("trim", Box::new(TrimHelper));
+        handlebars.register_helper("concat", Box::new(ConcatHelper));
+
+        test_against_configs(&handlebars, "{{trim Region.Key}}", "TEST");
+        assert_eq!(
+            render_fixture(
+                &handlebars,
+                "[{{trim (concat \" \" Region.Key \"\\t\\n\")}}]"
+            ),
+            "[TEST]"
+        );
+    }
+}
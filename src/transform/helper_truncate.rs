@@ -0,0 +1,63 @@
+use crate::transform::{integer_param, string_param};
+use handlebars::*;
+
+#[derive(Clone, Copy)]
+pub struct TruncateHelper;
+
+impl HelperDef for TruncateHelper {
+    // Shortens a string to at most a number of characters, ending it with the optional suffix
+    // when it is cut
+    // Usage: {{truncate Name 63}} {{truncate Description 20 "..."}}
+    fn call<'reg: 'rc, 'rc, 'ctx>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars,
+        _: &'ctx Context,
+        _: &mut RenderContext<'reg, 'ctx>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let value = string_param(h, 0, "truncate")?;
+        let length = integer_param(h, 1, "truncate")?
+            .ok_or_else(|| RenderError::new("Param not found for helper \"truncate\""))?
+            .max(0) as usize;
+        let suffix = match h.param(2) {
+            Some(_) => string_param(h, 2, "truncate")?.unwrap_or_default(),
+            None => String::new(),
+        };
+
+        if let Some(value) = value {
+            if value.chars().count() <= length {
+                out.write(&value)?;
+            } else {
+                let suffix_len = suffix.chars().count().min(length);
+                let kept = value.chars().take(length - suffix_len).collect::<String>();
+                out.write(&kept)?;
+                out.write(&suffix.chars().take(suffix_len).collect::<String>())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::test::test_against_configs;
+
+    #[test]
+    fn test_truncate() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("truncate", Box::new(TruncateHelper));
+
+        let templates = vec![
+            ("{{truncate UpperCaseString 5}}", "UPPER"),
+            ("{{truncate UpperCaseString 6 \"...\"}}", "UPP..."),
+            ("{{truncate UpperCaseString 9 \"...\"}}", "UPPERCASE"),
+            ("{{truncate UpperCaseString 2 \"...\"}}", ".."),
+            ("{{truncate (truncate UpperCaseString 7) 4}}", "UPPE"),
+        ];
+        for (template, expected) in templates {
+            test_against_configs(&handlebars, template, expected)
+        }
+    }
+}
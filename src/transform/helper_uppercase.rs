@@ -0,0 +1,46 @@
+use crate::transform::string_param;
+use handlebars::*;
+
+#[derive(Clone, Copy)]
+pub struct UppercaseHelper;
+
+impl HelperDef for UppercaseHelper {
+    fn call<'reg: 'rc, 'rc, 'ctx>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars,
+        _: &'ctx Context,
+        _: &mut RenderContext<'reg, 'ctx>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        if let Some(s) = string_param(h, 0, "uppercase")? {
+            out.write(&s.to_uppercase())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::helper_lowercase::LowercaseHelper;
+    use crate::transform::test::test_against_configs;
+
+    #[test]
+    fn test_uppercase() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("uppercase", Box::new(UppercaseHelper));
+        handlebars.register_helper("lowercase", Box::new(LowercaseHelper));
+
+        test_against_configs(
+            &handlebars,
+            "{{uppercase NonSlashService.notAnEndpoint}}",
+            "NO-PROTOCOL.NO-SLASH.COM",
+        );
+        test_against_configs(
+            &handlebars,
+            "{{uppercase (lowercase UpperCaseString)}}",
+            "UPPERCASE",
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use crate::transform::{string_param, url_param};
+use handlebars::*;
+
+#[derive(Clone, Copy)]
+pub struct UrlJoinHelper;
+
+impl HelperDef for UrlJoinHelper {
+    // Appends path segments to a URL, with a single slash between each. A trailing slash on the
+    // last param is kept
+    // Usage: {{url-join Service.endpoint "api/v1" Service.resource}}
+    fn call<'reg: 'rc, 'rc, 'ctx>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars,
+        _: &'ctx Context,
+        _: &mut RenderContext<'reg, 'ctx>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let mut url = match url_param(h, 0, "url-join")? {
+            Some(url) => url,
+            None => return Ok(()),
+        };
+
+        let mut parts = Vec::new();
+        for index in 1..h.params().len() {
+            if let Some(part) = string_param(h, index, "url-join")? {
+                parts.push(part);
+            }
+        }
+        let trailing_slash = parts.last().is_some_and(|part| part.ends_with('/'));
+
+        {
+            let mut segments = url.path_segments_mut().map_err(|_| {
+                RenderError::new(format!(
+                    "Param can not have a path for helper \"url-join\": {:?}",
+                    h.params()[0].value()
+                ))
+            })?;
+            segments.pop_if_empty();
+            for part in &parts {
+                segments.extend(part.split('/').filter(|s| !s.is_empty()));
+            }
+            if trailing_slash {
+                segments.push("");
+            }
+        }
+
+        out.write(url.as_str())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::test::{test_against_configs, test_error_against_configs};
+
+    #[test]
+    fn test_url_join() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("url-join", Box::new(UrlJoinHelper));
+
+        let templates = vec![
+            (
+                "{{url-join NonSlashService.endpoint \"api/v1\" Region.Key}}",
+                "https://nonslash.com/api/v1/TEST",
+            ),
+            (
+                "{{url-join SlashService.endpoint \"/api/\" \"/users/\"}}",
+                "https://slash.com/api/users/",
+            ),
+            (
+                "{{url-join PathService.trailingSlash \"more\" Missing}}",
+                "https://trailing-path.com/path/should-still-remove/more",
+            ),
+            (
+                "{{url-join PathService.endpoint \"a b\"}}",
+                "https://path.com/path/remove-this/a%20b",
+            ),
+        ];
+
+        for (template, expected) in templates {
+            test_against_configs(&handlebars, template, expected)
+        }
+
+        test_error_against_configs(
+            &handlebars,
+            "{{url-join NonSlashService.notAnEndpoint \"api\"}}",
+            "Param is not a valid url for helper \"url-join\": \"no-protocol.no-slash.com\" (relative URL without a base)",
+        );
+        assert!(handlebars
+            .render_template(
+                "{{url-join Url \"api\"}}",
+                &serde_json::json!({"Url": "mailto:ops@example.com"})
+            )
+            .is_err());
+    }
+}
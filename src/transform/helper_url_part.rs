@@ -0,0 +1,67 @@
+use crate::transform::{url_param, UrlPartFn};
+use handlebars::*;
+
+/// Writes a part of a URL, e.g. {{url-host DB.Url}}. Registered once per part. Nothing is written
+/// when the URL doesn't have the part
+#[derive(Clone, Copy)]
+pub struct UrlPartHelper {
+    pub name: &'static str,
+    pub part: UrlPartFn,
+}
+
+impl HelperDef for UrlPartHelper {
+    fn call<'reg: 'rc, 'rc, 'ctx>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars,
+        _: &'ctx Context,
+        _: &mut RenderContext<'reg, 'ctx>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        if let Some(part) = url_param(h, 0, self.name)?.and_then(|url| (self.part)(&url)) {
+            out.write(&part)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::test::{test_against_configs, test_error_against_configs};
+
+    #[test]
+    fn test_url_parts() {
+        let mut handlebars = Handlebars::new();
+        for (name, part) in crate::transform::URL_PART_HELPERS {
+            handlebars.register_helper(name, Box::new(UrlPartHelper { name, part: *part }));
+        }
+
+        let templates = vec![
+            ("{{url-host NonSlashService.endpoint}}", "nonslash.com"),
+            ("{{url-scheme SlashService.endpoint}}", "https"),
+            ("{{url-path PathService.endpoint}}", "/path/remove-this"),
+            ("{{url-path NonSlashService.endpoint}}", "/"),
+        ];
+
+        for (template, expected) in templates {
+            test_against_configs(&handlebars, template, expected)
+        }
+
+        assert_eq!(
+            handlebars
+                .render_template(
+                    "{{url-host Url}}",
+                    &serde_json::json!({"Url": "mailto:ops@example.com"})
+                )
+                .unwrap(),
+            ""
+        );
+
+        test_error_against_configs(
+            &handlebars,
+            "{{url-host NonSlashService.notAnEndpoint}}",
+            "Param is not a valid url for helper \"url-host\": \"no-protocol.no-slash.com\" (relative URL without a base)",
+        );
+    }
+}
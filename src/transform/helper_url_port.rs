@@ -0,0 +1,77 @@
+use crate::transform::{string_param, url_param};
+use handlebars::*;
+
+#[derive(Clone, Copy)]
+pub struct UrlPortHelper;
+
+impl HelperDef for UrlPortHelper {
+    // Writes the port of a URL, or the default port of its scheme when it doesn't give one, e.g.
+    // 443 for https. The second param is the port for schemes without a known default
+    // Usage: {{url-port Service.endpoint}} {{url-port DB.Url 5432}}
+    fn call<'reg: 'rc, 'rc, 'ctx>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars,
+        _: &'ctx Context,
+        _: &mut RenderContext<'reg, 'ctx>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let url = match url_param(h, 0, "url-port")? {
+            Some(url) => url,
+            None => return Ok(()),
+        };
+
+        let port = match url.port_or_known_default() {
+            Some(port) => Some(port.to_string()),
+            None if h.param(1).is_some() => string_param(h, 1, "url-port")?,
+            None => None,
+        };
+        if let Some(port) = port {
+            out.write(&port)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::test::{test_against_configs, test_error_against_configs};
+
+    #[test]
+    fn test_url_port() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("url-port", Box::new(UrlPortHelper));
+
+        test_against_configs(&handlebars, "{{url-port SlashService.endpoint}}", "443");
+        test_against_configs(
+            &handlebars,
+            "{{url-port SlashService.endpoint 8443}}",
+            "443",
+        );
+
+        let config = serde_json::json!({
+            "Web": "http://web.internal:8080/",
+            "Plain": "http://web.internal/",
+            "DB": "postgres://db.internal/app",
+        });
+        let templates = vec![
+            ("{{url-port Web}}", "8080"),
+            ("{{url-port Plain}}", "80"),
+            ("{{url-port DB}}", ""),
+            ("{{url-port DB 5432}}", "5432"),
+        ];
+        for (template, expected) in templates {
+            assert_eq!(
+                handlebars.render_template(template, &config).unwrap(),
+                expected
+            );
+        }
+
+        test_error_against_configs(
+            &handlebars,
+            "{{url-port Region.Key}}",
+            "Param is not a valid url for helper \"url-port\": \"TEST\" (relative URL without a base)",
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use crate::transform::{string_param, url_param};
+use handlebars::*;
+use url::Url;
+
+#[derive(Clone, Copy)]
+pub struct UrlSetSchemeHelper;
+
+impl HelperDef for UrlSetSchemeHelper {
+    // Replaces the scheme of a URL, including between web and other schemes, e.g. https to redis.
+    // A port which is the default for the new scheme is dropped
+    // Usage: {{url-set-scheme Service.endpoint "wss"}}
+    fn call<'reg: 'rc, 'rc, 'ctx>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars,
+        _: &'ctx Context,
+        _: &mut RenderContext<'reg, 'ctx>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let url = url_param(h, 0, "url-set-scheme")?;
+        let scheme = string_param(h, 1, "url-set-scheme")?.unwrap_or_default();
+
+        if let Some(url) = url {
+            // Url::set_scheme refuses to change between special and other schemes, so parse the
+            // URL again with the new scheme in its place
+            let replaced = format!("{}{}", scheme, &url.as_str()[url.scheme().len()..]);
+            let replaced = Url::parse(&replaced)
+                .ok()
+                .filter(|u| u.scheme() == scheme.to_lowercase())
+                .ok_or_else(|| {
+                    RenderError::new(format!(
+                        "Param is not a valid scheme for helper \"url-set-scheme\": {:?}",
+                        scheme
+                    ))
+                })?;
+            out.write(replaced.as_str())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::test::{test_against_configs, test_error_against_configs};
+
+    #[test]
+    fn test_url_set_scheme() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("url-set-scheme", Box::new(UrlSetSchemeHelper));
+
+        let templates = vec![
+            (
+                "{{url-set-scheme NonSlashService.endpoint \"wss\"}}",
+                "wss://nonslash.com/",
+            ),
+            (
+                "{{url-set-scheme PathService.endpoint \"redis\"}}",
+                "redis://path.com/path/remove-this",
+            ),
+        ];
+
+        for (template, expected) in templates {
+            test_against_configs(&handlebars, template, expected)
+        }
+
+        assert_eq!(
+            handlebars
+                .render_template(
+                    "{{url-set-scheme Url \"https\"}}",
+                    &serde_json::json!({"Url": "http://web.internal:443/"})
+                )
+                .unwrap(),
+            "https://web.internal/"
+        );
+
+        test_error_against_configs(
+            &handlebars,
+            "{{url-set-scheme SlashService.endpoint \"no scheme\"}}",
+            "Param is not a valid scheme for helper \"url-set-scheme\": \"no scheme\"",
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use crate::transform::url_param;
+use handlebars::*;
+use serde_json::value::Value as Json;
+
+#[derive(Clone, Copy)]
+pub struct UrlWithQueryHelper;
+
+impl HelperDef for UrlWithQueryHelper {
+    // Sets query parameters on a URL from the hash, or from an object given as the second param,
+    // replacing any already there with the same name. Null values are skipped
+    // Usage: {{url-with-query DB.Url timeout=30 ssl=true}} {{url-with-query DB.Url DB.Options}}
+    fn call<'reg: 'rc, 'rc, 'ctx>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars,
+        _: &'ctx Context,
+        _: &mut RenderContext<'reg, 'ctx>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let mut url = match url_param(h, 0, "url-with-query")? {
+            Some(url) => url,
+            None => return Ok(()),
+        };
+
+        let mut params: Vec<(String, &Json)> = Vec::new();
+        match h.param(1).map(|p| p.value()) {
+            None | Some(Json::Null) => {}
+            Some(Json::Object(map)) => params.extend(map.iter().map(|(k, v)| (k.clone(), v))),
+            Some(value) => {
+                return Err(RenderError::new(format!(
+                    "Param type is not object for helper \"url-with-query\": {:?}",
+                    value
+                )))
+            }
+        }
+        params.extend(h.hash().iter().map(|(k, v)| (k.to_string(), v.value())));
+
+        let mut query = Vec::new();
+        for (name, value) in params {
+            let value = match value {
+                Json::Null => continue,
+                Json::String(s) => s.to_owned(),
+                v @ Json::Number(_) | v @ Json::Bool(_) => v.to_string(),
+                v => {
+                    return Err(RenderError::new(format!(
+                        "Query param \"{}\" is not a string for helper \"url-with-query\": {:?}",
+                        name, v
+                    )))
+                }
+            };
+            query.retain(|(n, _): &(String, String)| *n != name);
+            query.push((name, value));
+        }
+
+        let existing: Vec<(String, String)> = url
+            .query_pairs()
+            .into_owned()
+            .filter(|(name, _)| !query.iter().any(|(n, _)| n == name))
+            .collect();
+        if existing.is_empty() && query.is_empty() {
+            url.set_query(None);
+        } else {
+            url.query_pairs_mut()
+                .clear()
+                .extend_pairs(existing)
+                .extend_pairs(query);
+        }
+
+        out.write(url.as_str())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::test::{test_against_configs, test_error_against_configs};
+
+    #[test]
+    fn test_url_with_query() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("url-with-query", Box::new(UrlWithQueryHelper));
+
+        let templates = vec![
+            (
+                "{{url-with-query NonSlashService.endpoint region=Region.Key ssl=true}}",
+                "https://nonslash.com/?region=TEST&ssl=true",
+            ),
+            (
+                "{{url-with-query PathService.endpoint Region port=Memcache.Servers.0.Port}}",
+                "https://path.com/path/remove-this?Key=TEST&port=1122",
+            ),
+            (
+                "{{url-with-query SlashService.endpoint missing=Missing}}",
+                "https://slash.com/",
+            ),
+        ];
+
+        for (template, expected) in templates {
+            test_against_configs(&handlebars, template, expected)
+        }
+
+        let config = serde_json::json!({
+            "Url": "postgres://db.internal/app?sslmode=disable&timeout=10",
+            "Options": {"sslmode": "require", "app name": "hogan & co"},
+        });
+        assert_eq!(
+            handlebars
+                .render_template("{{url-with-query Url Options timeout=30}}", &config)
+                .unwrap(),
+            "postgres://db.internal/app?app+name=hogan+%26+co&sslmode=require&timeout=30"
+        );
+
+        test_error_against_configs(
+            &handlebars,
+            "{{url-with-query SlashService.endpoint servers=Memcache.Servers}}",
+            "Query param \"servers\" is not a string for helper \"url-with-query\": Array([Object({\"Endpoint\": String(\"192.168.1.100\"), \"Port\": String(\"1122\")}), Object({\"Endpoint\": String(\"192.168.1.101\"), \"Port\": String(\"1122\")}), Object({\"Endpoint\": String(\"192.168.1.102\"), \"Port\": String(\"1122\")})])",
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use crate::transform::string_param;
+use handlebars::*;
+use uuid::Uuid;
+
+#[derive(Clone, Copy)]
+pub struct UuidV5Helper;
+
+impl HelperDef for UuidV5Helper {
+    // Writes the version 5 UUID of a name in a namespace, which is the same on every render. The
+    // namespace is a UUID, or one of the standard dns, url, oid and x500 namespaces
+    // Usage: {{uuid-v5 "dns" Service.Host}} {{uuid-v5 App.Namespace Region.Key}}
+    fn call<'reg: 'rc, 'rc, 'ctx>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars,
+        _: &'ctx Context,
+        _: &mut RenderContext<'reg, 'ctx>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let namespace = string_param(h, 0, "uuid-v5")?;
+        let name = string_param(h, 1, "uuid-v5")?;
+
+        let namespace = match namespace.as_deref() {
+            Some("dns") => Uuid::NAMESPACE_DNS,
+            Some("url") => Uuid::NAMESPACE_URL,
+            Some("oid") => Uuid::NAMESPACE_OID,
+            Some("x500") => Uuid::NAMESPACE_X500,
+            Some(namespace) => Uuid::parse_str(namespace).map_err(|e| {
+                RenderError::new(format!(
+                    "Param is not a uuid namespace for helper \"uuid-v5\": {:?} ({})",
+                    namespace, e
+                ))
+            })?,
+            None => return Ok(()),
+        };
+
+        if let Some(name) = name {
+            out.write(&Uuid::new_v5(&namespace, name.as_bytes()).to_string())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transform::test::{test_against_configs, test_error_against_configs};
+
+    #[test]
+    fn test_uuid_v5() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("uuid-v5", Box::new(UuidV5Helper));
+
+        let templates = vec![
+            (
+                "{{uuid-v5 \"url\" NonSlashService.endpoint}}",
+                "e018b651-dd83-5635-a380-c54e90c6779a",
+            ),
+            (
+                "{{uuid-v5 \"6ba7b810-9dad-11d1-80b4-00c04fd430c8\" Region.Key}}",
+                "d241d181-ef3d-5407-9a7e-56739dd7ae1f",
+            ),
+            ("{{uuid-v5 \"dns\" Missing}}", ""),
+        ];
+
+        for (template, expected) in templates {
+            test_against_configs(&handlebars, template, expected)
+        }
+
+        assert_eq!(
+            handlebars
+                .render_template(
+                    "{{uuid-v5 \"dns\" Host}}",
+                    &serde_json::json!({"Host": "nonslash.com"})
+                )
+                .unwrap(),
+            "fc105470-62a6-5308-98e2-cbcaff92593b"
+        );
+
+        test_error_against_configs(
+            &handlebars,
+            "{{uuid-v5 Region.Key Region.Key}}",
+            "Param is not a uuid namespace for helper \"uuid-v5\": \"TEST\" (invalid length: expected one of [36, 32], found 4)",
+        );
+    }
+}
@@ -1,21 +1,95 @@
-use handlebars::Handlebars;
+use crate::error::HoganError;
+use anyhow::{Context, Result};
+use handlebars::{
+    Context as HbContext, Handlebars, Helper, HelperResult, Output, RenderContext, RenderError,
+    Renderable,
+};
+use serde_json::Value as Json;
+use std::cmp::Ordering;
+use std::fs;
+use std::path::{Path, PathBuf};
+use url::Url;
+use walkdir::WalkDir;
 
+pub mod escape;
+mod helper_and;
+mod helper_base64_decode;
 mod helper_comma_delimited_list;
+mod helper_compare;
+mod helper_concat;
+mod helper_contains;
+mod helper_default;
+mod helper_empty;
+mod helper_encode;
 mod helper_equal;
+mod helper_escape;
+mod helper_in;
+mod helper_join;
 mod helper_lowercase;
+mod helper_matches;
+mod helper_not;
+mod helper_not_equal;
 mod helper_or;
+mod helper_pad;
+mod helper_regex_replace;
+mod helper_replace;
+mod helper_split;
+mod helper_substring;
+mod helper_to_json;
+mod helper_to_xml;
+mod helper_to_yaml;
+mod helper_trim;
+mod helper_truncate;
+mod helper_uppercase;
 mod helper_url_add_slash;
+mod helper_url_join;
+mod helper_url_part;
+mod helper_url_port;
 mod helper_url_rm_path;
 mod helper_url_rm_slash;
+mod helper_url_set_scheme;
+mod helper_url_with_query;
+mod helper_uuid_v5;
 mod helper_yaml_string;
 
+use self::helper_and::AndHelper;
+use self::helper_base64_decode::Base64DecodeHelper;
 use self::helper_comma_delimited_list::CommaDelimitedListHelper;
+use self::helper_compare::CompareHelper;
+use self::helper_concat::ConcatHelper;
+use self::helper_contains::ContainsHelper;
+use self::helper_default::DefaultHelper;
+use self::helper_empty::EmptyHelper;
+use self::helper_encode::EncodeHelper;
 use self::helper_equal::EqualHelper;
+use self::helper_escape::EscapeHelper;
+use self::helper_in::InHelper;
+use self::helper_join::JoinHelper;
 use self::helper_lowercase::LowercaseHelper;
+use self::helper_matches::MatchesHelper;
+use self::helper_not::NotHelper;
+use self::helper_not_equal::NotEqualHelper;
 use self::helper_or::OrHelper;
+use self::helper_pad::PadHelper;
+use self::helper_regex_replace::RegexReplaceHelper;
+use self::helper_replace::ReplaceHelper;
+use self::helper_split::SplitHelper;
+use self::helper_substring::SubstringHelper;
+use self::helper_to_json::ToJsonHelper;
+use self::helper_to_xml::ToXmlHelper;
+use self::helper_to_yaml::ToYamlHelper;
+use self::helper_trim::TrimHelper;
+use self::helper_truncate::TruncateHelper;
+use self::helper_uppercase::UppercaseHelper;
 use self::helper_url_add_slash::UrlAddSlashHelper;
+use self::helper_url_join::UrlJoinHelper;
+use self::helper_url_part::UrlPartHelper;
+use self::helper_url_port::UrlPortHelper;
 use self::helper_url_rm_path::UrlRmPathHelper;
 use self::helper_url_rm_slash::UrlRmSlashHelper;
+use self::helper_url_set_scheme::UrlSetSchemeHelper;
+use self::helper_url_with_query::UrlWithQueryHelper;
+use self::helper_uuid_v5::UuidV5Helper;
 use self::helper_yaml_string::YamlStringHelper;
 
 //This fn was changed here https://github.com/sunng87/handlebars-rust/pull/366 which added additional characters to the list
@@ -34,29 +108,406 @@ pub fn old_escape_html(s: &str) -> String {
     output
 }
 
+/// Escapes a string for a format, see `escape`
+pub type EscapeFn = fn(&str) -> String;
+
+/// Helpers writing their param escaped for a format
+const ESCAPE_HELPERS: &[(&str, EscapeFn)] = &[
+    ("xml-attr", escape::xml_attr),
+    ("xml-text", escape::xml_text),
+    ("json-string", escape::json_string),
+    ("properties-value", escape::properties_value),
+    ("shell-quote", escape::shell_quote),
+];
+
+/// Reads a part of a URL, see `UrlPartHelper`
+pub type UrlPartFn = fn(&Url) -> Option<String>;
+
+/// Helpers writing a part of a URL
+const URL_PART_HELPERS: &[(&str, UrlPartFn)] = &[
+    ("url-host", |url| url.host_str().map(str::to_owned)),
+    ("url-path", |url| Some(url.path().to_owned())),
+    ("url-scheme", |url| Some(url.scheme().to_owned())),
+];
+
+/// Encodes or hashes a string, see `EncodeHelper`
+pub type EncodeFn = fn(&str) -> String;
+
+/// Helpers writing their param encoded or hashed
+const ENCODE_HELPERS: &[(&str, EncodeFn)] = &[
+    ("base64-encode", helper_encode::base64_encode),
+    ("hex", helper_encode::hex),
+    ("md5", helper_encode::md5),
+    ("sha1", helper_encode::sha1),
+    ("sha256", helper_encode::sha256),
+];
+
+/// Accepts some orderings of two values, see `CompareHelper`
+pub type CompareFn = fn(Ordering) -> bool;
+
+/// The comparison helpers, each with the orderings of its params that it accepts
+const COMPARE_HELPERS: &[(&str, CompareFn)] = &[
+    ("gt", Ordering::is_gt),
+    ("gte", Ordering::is_ge),
+    ("lt", Ordering::is_lt),
+    ("lte", Ordering::is_le),
+];
+
 pub fn handlebars<'a>(strict: bool) -> Handlebars<'a> {
     let mut handlebars = Handlebars::new();
     handlebars.set_strict_mode(strict);
+    handlebars.register_helper("and", Box::new(AndHelper));
+    handlebars.register_helper("base64-decode", Box::new(Base64DecodeHelper));
     handlebars.register_helper("comma-list", Box::new(CommaDelimitedListHelper));
-    handlebars.register_helper("equal", Box::new(EqualHelper));
+    handlebars.register_helper("concat", Box::new(ConcatHelper));
+    handlebars.register_helper("contains", Box::new(ContainsHelper));
+    handlebars.register_helper("default", Box::new(DefaultHelper));
+    handlebars.register_helper("empty", Box::new(EmptyHelper));
     handlebars.register_helper("eq", Box::new(EqualHelper));
+    handlebars.register_helper("equal", Box::new(EqualHelper));
+    handlebars.register_helper("in", Box::new(InHelper));
+    handlebars.register_helper("join", Box::new(JoinHelper));
     handlebars.register_helper("lowercase", Box::new(LowercaseHelper));
+    handlebars.register_helper("matches", Box::new(MatchesHelper));
+    handlebars.register_helper("ne", Box::new(NotEqualHelper));
+    handlebars.register_helper("not", Box::new(NotHelper));
     handlebars.register_helper("or", Box::new(OrHelper));
+    handlebars.register_helper("pad", Box::new(PadHelper));
+    handlebars.register_helper("regex-replace", Box::new(RegexReplaceHelper));
+    handlebars.register_helper("replace", Box::new(ReplaceHelper));
+    handlebars.register_helper("split", Box::new(SplitHelper));
+    handlebars.register_helper("substring", Box::new(SubstringHelper));
+    handlebars.register_helper("to-json", Box::new(ToJsonHelper));
+    handlebars.register_helper("to-xml", Box::new(ToXmlHelper));
+    handlebars.register_helper("to-yaml", Box::new(ToYamlHelper));
+    handlebars.register_helper("trim", Box::new(TrimHelper));
+    handlebars.register_helper("truncate", Box::new(TruncateHelper));
+    handlebars.register_helper("uppercase", Box::new(UppercaseHelper));
     handlebars.register_helper("url-add-slash", Box::new(UrlAddSlashHelper));
+    handlebars.register_helper("url-join", Box::new(UrlJoinHelper));
+    handlebars.register_helper("url-port", Box::new(UrlPortHelper));
     handlebars.register_helper("url-rm-path", Box::new(UrlRmPathHelper));
     handlebars.register_helper("url-rm-slash", Box::new(UrlRmSlashHelper));
+    handlebars.register_helper("url-set-scheme", Box::new(UrlSetSchemeHelper));
+    handlebars.register_helper("url-with-query", Box::new(UrlWithQueryHelper));
+    handlebars.register_helper("uuid-v5", Box::new(UuidV5Helper));
     handlebars.register_helper("yaml-string", Box::new(YamlStringHelper));
-    handlebars.register_escape_fn(old_escape_html);
+    for (name, escape) in ESCAPE_HELPERS {
+        handlebars.register_helper(
+            name,
+            Box::new(EscapeHelper {
+                name,
+                escape: *escape,
+            }),
+        );
+    }
+    for (name, encode) in ENCODE_HELPERS {
+        handlebars.register_helper(
+            name,
+            Box::new(EncodeHelper {
+                name,
+                encode: *encode,
+            }),
+        );
+    }
+    for (name, accept) in COMPARE_HELPERS {
+        handlebars.register_helper(
+            name,
+            Box::new(CompareHelper {
+                name,
+                accept: *accept,
+            }),
+        );
+    }
+    for (name, part) in URL_PART_HELPERS {
+        handlebars.register_helper(name, Box::new(UrlPartHelper { name, part: *part }));
+    }
+    handlebars.register_escape_fn(escape::escape);
     handlebars
 }
 
+/// The minimum and, if limited, maximum number of parameters accepted by the helpers registered
+/// in `handlebars`, including the handlebars built in helpers
+const HELPER_ARITY: &[(&str, usize, Option<usize>)] = &[
+    ("if", 1, Some(1)),
+    ("unless", 1, Some(1)),
+    ("each", 1, Some(1)),
+    ("with", 1, Some(1)),
+    ("lookup", 2, Some(2)),
+    ("and", 2, None),
+    ("base64-decode", 1, Some(1)),
+    ("base64-encode", 1, Some(1)),
+    ("comma-list", 1, Some(1)),
+    ("concat", 1, None),
+    ("contains", 2, Some(2)),
+    ("default", 2, None),
+    ("empty", 1, Some(1)),
+    ("eq", 2, Some(2)),
+    ("equal", 2, Some(2)),
+    ("gt", 2, Some(2)),
+    ("gte", 2, Some(2)),
+    ("hex", 1, Some(1)),
+    ("in", 2, None),
+    ("join", 1, Some(2)),
+    ("json-string", 1, Some(1)),
+    ("lowercase", 1, Some(1)),
+    ("lt", 2, Some(2)),
+    ("lte", 2, Some(2)),
+    ("matches", 2, Some(2)),
+    ("md5", 1, Some(1)),
+    ("ne", 2, Some(2)),
+    ("not", 1, Some(1)),
+    ("or", 2, None),
+    ("pad", 2, Some(3)),
+    ("properties-value", 1, Some(1)),
+    ("regex-replace", 3, Some(3)),
+    ("replace", 3, Some(3)),
+    ("sha1", 1, Some(1)),
+    ("sha256", 1, Some(1)),
+    ("shell-quote", 1, Some(1)),
+    ("split", 2, Some(2)),
+    ("substring", 2, Some(3)),
+    ("to-json", 1, Some(1)),
+    ("to-xml", 1, Some(1)),
+    ("to-yaml", 1, Some(1)),
+    ("trim", 1, Some(1)),
+    ("truncate", 2, Some(3)),
+    ("uppercase", 1, Some(1)),
+    ("url-add-slash", 1, Some(1)),
+    ("url-host", 1, Some(1)),
+    ("url-join", 2, None),
+    ("url-path", 1, Some(1)),
+    ("url-port", 1, Some(2)),
+    ("url-rm-path", 1, Some(1)),
+    ("url-rm-slash", 1, Some(1)),
+    ("url-scheme", 1, Some(1)),
+    ("url-set-scheme", 2, Some(2)),
+    ("url-with-query", 1, Some(2)),
+    ("uuid-v5", 2, Some(2)),
+    ("xml-attr", 1, Some(1)),
+    ("xml-text", 1, Some(1)),
+    ("yaml-string", 1, Some(1)),
+];
+
+/// Returns the minimum and maximum number of parameters a helper accepts, if known
+pub fn helper_arity(name: &str) -> Option<(usize, Option<usize>)> {
+    HELPER_ARITY
+        .iter()
+        .find(|(helper, _, _)| *helper == name)
+        .map(|(_, min, max)| (*min, *max))
+}
+
+/// Checks a helper was given a number of params within its `HELPER_ARITY`
+pub(crate) fn check_arity(h: &Helper, helper: &str) -> Result<(), RenderError> {
+    let count = h.params().len();
+    let plural = |n: usize| if n == 1 { "parameter" } else { "parameters" };
+    match helper_arity(helper) {
+        Some((min, Some(max))) if min == max && count != min => Err(RenderError::new(format!(
+            "'{}' requires exactly {} {}",
+            helper,
+            min,
+            plural(min)
+        ))),
+        Some((min, _)) if count < min => Err(RenderError::new(format!(
+            "'{}' requires at least {} {}",
+            helper,
+            min,
+            plural(min)
+        ))),
+        Some((_, Some(max))) if count > max => Err(RenderError::new(format!(
+            "'{}' accepts at most {} {}",
+            helper,
+            max,
+            plural(max)
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Whether a value counts as true for the logic helpers. Missing values, false, 0 and empty
+/// strings, arrays and objects are false. The "true" or "" written by a logic helper used as a
+/// subexpression reads back the same
+pub(crate) fn is_truthy(value: &Json) -> bool {
+    match value {
+        Json::Null => false,
+        Json::Bool(b) => *b,
+        Json::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        Json::String(s) => !s.is_empty(),
+        Json::Array(a) => !a.is_empty(),
+        Json::Object(o) => !o.is_empty(),
+    }
+}
+
+/// Renders the result of a logic helper as `OrHelper` does. As a block the template or its else
+/// is rendered, otherwise "true" is written for a true condition and nothing for a false one
+pub(crate) fn render_condition<'reg: 'rc, 'rc, 'ctx>(
+    condition: bool,
+    h: &Helper<'reg, 'rc>,
+    r: &'reg Handlebars,
+    ctx: &'ctx HbContext,
+    rc: &mut RenderContext<'reg, 'ctx>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    if h.is_block() {
+        let template = if condition { h.template() } else { h.inverse() };
+
+        match template {
+            Some(t) => t.render(r, ctx, rc, out),
+            None => Ok(()),
+        }
+    } else {
+        if condition {
+            out.write(&condition.to_string())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads an optional whole number hash parameter of a helper, e.g. indent=4
+pub(crate) fn usize_hash(
+    h: &Helper,
+    helper: &str,
+    name: &str,
+) -> Result<Option<usize>, RenderError> {
+    match h.hash_get(name) {
+        None => Ok(None),
+        Some(value) => value
+            .value()
+            .as_u64()
+            .map(|v| Some(v as usize))
+            .ok_or_else(|| {
+                RenderError::new(format!(
+                    "Param \"{}\" is not a whole number for helper \"{}\": {:?}",
+                    name,
+                    helper,
+                    value.value()
+                ))
+            }),
+    }
+}
+
+/// Reads a param of a helper as a string, converting numbers and booleans. Null is None
+pub(crate) fn string_param(
+    h: &Helper,
+    index: usize,
+    helper: &str,
+) -> Result<Option<String>, RenderError> {
+    let value = h
+        .param(index)
+        .ok_or_else(|| RenderError::new(format!("Param not found for helper \"{}\"", helper)))?;
+
+    match *value.value() {
+        Json::String(ref s) => Ok(Some(s.to_owned())),
+        ref v @ Json::Number(_) | ref v @ Json::Bool(_) => Ok(Some(v.to_string())),
+        Json::Null => Ok(None),
+        _ => Err(RenderError::new(format!(
+            "Param type is not string for helper \"{}\": {:?}",
+            helper, value
+        ))),
+    }
+}
+
+/// Reads a param of a helper as an absolute URL. Null is None
+pub(crate) fn url_param(
+    h: &Helper,
+    index: usize,
+    helper: &str,
+) -> Result<Option<Url>, RenderError> {
+    match string_param(h, index, helper)? {
+        Some(s) => Url::parse(&s).map(Some).map_err(|e| {
+            RenderError::new(format!(
+                "Param is not a valid url for helper \"{}\": {:?} ({})",
+                helper, s, e
+            ))
+        }),
+        None => Ok(None),
+    }
+}
+
+/// Reads a whole number param of a helper, or None when the helper was given fewer params
+pub(crate) fn integer_param(
+    h: &Helper,
+    index: usize,
+    helper: &str,
+) -> Result<Option<i64>, RenderError> {
+    match h.param(index) {
+        None => Ok(None),
+        Some(value) => value.value().as_i64().map(Some).ok_or_else(|| {
+            RenderError::new(format!(
+                "Param type is not a whole number for helper \"{}\": {:?}",
+                helper, value
+            ))
+        }),
+    }
+}
+
+/// Indents every line after the first, so output spanning several lines keeps the indentation of
+/// the template line it starts on
+pub(crate) fn offset_lines(text: &str, offset: usize) -> String {
+    text.replace('\n', &format!("\n{}", " ".repeat(offset)))
+}
+
+/// Name of the directory partials are discovered in, next to the templates or in the configs repo
+pub const PARTIALS_DIR: &str = "_partials";
+
+/// Registers every file below the directory as a partial, see `register_partial_files`
+pub fn register_partials_dir(handlebars: &mut Handlebars, dir: &Path) -> Result<usize> {
+    let mut files = Vec::new();
+    for entry in WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
+        if entry.file_type().is_file() {
+            let contents = fs::read(entry.path())
+                .with_context(|| format!("Error reading partial {:?}", entry.path()))?;
+            let relative = entry
+                .path()
+                .strip_prefix(dir)
+                .unwrap_or_else(|_| entry.path());
+            files.push((relative.to_path_buf(), contents));
+        }
+    }
+
+    register_partial_files(handlebars, &files)
+}
+
+/// Registers each file as a partial named after its path relative to the partials directory
+/// without extensions, e.g. logging.hbs becomes {{> logging}} and db/sql.template.config {{> db/sql}}
+pub fn register_partial_files(
+    handlebars: &mut Handlebars,
+    files: &[(PathBuf, Vec<u8>)],
+) -> Result<usize> {
+    for (path, contents) in files {
+        let name = partial_name(path);
+        let source = String::from_utf8_lossy(contents);
+        debug!("Registering partial {} from {:?}", name, path);
+        handlebars
+            .register_partial(&name, source.as_ref())
+            .map_err(|e| HoganError::InvalidConfiguration {
+                param: "partials".to_string(),
+                msg: format!("Unable to register partial {} from {:?}: {}", name, path, e),
+            })?;
+    }
+
+    Ok(files.len())
+}
+
+fn partial_name(path: &Path) -> String {
+    let mut segments = path
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    if let Some(file_name) = segments.last_mut() {
+        file_name.truncate(file_name.find('.').unwrap_or(file_name.len()));
+    }
+    segments.join("/")
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use serde_json::{self, Value};
 
-    fn config_fixture() -> Value {
-        let mut config: Value = serde_json::from_str(&include_str!(
+    pub(crate) fn config_fixture() -> Value {
+        let mut config: Value = serde_json::from_str(include_str!(
             "../../tests/fixtures/configs/config.TEST.json"
         ))
         .unwrap();
@@ -73,16 +524,70 @@ mod test {
         assert_eq!(&null_rendered.unwrap(), "");
     }
 
+    /// Renders against the fixture config only, for templates with output besides the values
+    pub(crate) fn render_fixture(handlebars: &Handlebars, template: &str) -> String {
+        handlebars
+            .render_template(template, &config_fixture())
+            .unwrap()
+    }
+
     pub(crate) fn test_error_against_configs(
         handlebars: &Handlebars,
         template: &str,
         expected: &str,
     ) {
         let config_rendered = handlebars.render_template(template, &config_fixture());
-        assert!(!config_rendered.is_ok());
+        assert!(config_rendered.is_err());
         assert_eq!(
             &config_rendered.unwrap_err().as_render_error().unwrap().desc,
             expected
         );
     }
+
+    #[test]
+    fn test_register_partial_files() {
+        let mut handlebars = handlebars(false);
+        let files = vec![
+            (
+                PathBuf::from("logging.hbs"),
+                b"<log level=\"{{lowercase Region.Key}}\" />".to_vec(),
+            ),
+            (
+                PathBuf::from("db/connection.template.config"),
+                b"{{DB.Endpoint}}".to_vec(),
+            ),
+        ];
+        assert_eq!(register_partial_files(&mut handlebars, &files).unwrap(), 2);
+
+        test_against_configs(
+            &handlebars,
+            "{{#if Region}}{{> logging}} {{> db/connection}}{{/if}}",
+            "<log level=\"test\" /> host-name\\TEST&quot;",
+        );
+    }
+
+    #[test]
+    fn test_register_partials_dir() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(temp_dir.path().join("nested")).unwrap();
+        fs::write(temp_dir.path().join("nested/region.hbs"), "{{Region.Key}}").unwrap();
+
+        let mut handlebars = handlebars(false);
+        assert_eq!(
+            register_partials_dir(&mut handlebars, temp_dir.path()).unwrap(),
+            1
+        );
+        test_against_configs(
+            &handlebars,
+            "{{#if Region}}{{> nested/region}}{{/if}}",
+            "TEST",
+        );
+    }
+
+    #[test]
+    fn test_invalid_partial() {
+        let mut handlebars = handlebars(false);
+        let files = vec![(PathBuf::from("broken.hbs"), b"{{#if}}".to_vec())];
+        assert!(register_partial_files(&mut handlebars, &files).is_err());
+    }
 }
@@ -0,0 +1,541 @@
+use crate::config::Environment;
+use crate::diff::join_path;
+use crate::template::{Template, RESERVED_KEY};
+use anyhow::{Context, Result};
+use handlebars::template::{
+    BlockParam, HelperTemplate, Parameter, Template as Ast, TemplateElement, TemplateMapping,
+};
+use handlebars::Handlebars;
+use serde_json::Value;
+
+/// Segment standing in for every element of an array or object iterated with #each
+pub const ANY_ELEMENT: &str = "*";
+
+/// A config path referenced by a template, dot separated like `diff::lookup` paths. Elements
+/// iterated with #each are written as *, e.g. Memcache.Servers.*.Endpoint
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Reference {
+    pub path: String,
+    /// Name of the partial the first reference is in, if it's not in the template itself
+    pub partial: Option<String>,
+    /// Position of the first reference, counting from 1
+    pub line: usize,
+    pub column: usize,
+    /// Only used as the condition of #if or #unless, so it may be missing
+    pub optional: bool,
+    /// Whether the value itself is rendered or passed to a helper, rather than only tested by #if
+    /// or used as the context of a block such as #each
+    pub value_used: bool,
+}
+
+/// How a path is used by a template
+#[derive(Clone, Copy, PartialEq)]
+enum Usage {
+    Value,
+    Condition,
+    Context,
+}
+
+/// The context a block renders with, and the block parameters it defines
+#[derive(Clone, Default)]
+struct Scope {
+    /// None when the context isn't a config path, e.g. the result of a subexpression
+    base: Option<String>,
+    params: Vec<(String, Option<String>)>,
+}
+
+struct Walker<'a, 'reg> {
+    handlebars: &'a Handlebars<'reg>,
+    /// Partials being walked, to stop recursive partials
+    partials: Vec<String>,
+    line_offset: usize,
+    references: Vec<Reference>,
+}
+
+/// Lists every config path the template references, in the order they first appear. Paths used
+/// in blocks, helper parameters, subexpressions and registered partials are included, while
+/// helper names, literals and @ variables such as @index are not
+pub fn references(handlebars: &Handlebars, template: &Template) -> Result<Vec<Reference>> {
+    source_references(handlebars, &template.contents, template.front_matter.lines)
+        .with_context(|| format!("Error parsing template {:?}", template.path))
+}
+
+/// Whether the template source, or a partial it uses, references the render metadata at the root
+/// of the data. A template which can't be parsed doesn't
+pub fn uses_render_metadata(handlebars: &Handlebars, source: &str) -> bool {
+    source_references(handlebars, source, 0)
+        .map(|references| {
+            references
+                .iter()
+                .any(|r| r.path.split('.').next() == Some(RESERVED_KEY))
+        })
+        .unwrap_or(false)
+}
+
+fn source_references(
+    handlebars: &Handlebars,
+    source: &str,
+    line_offset: usize,
+) -> Result<Vec<Reference>> {
+    let ast = Ast::compile2(source, true)?;
+    let mut walker = Walker {
+        handlebars,
+        partials: Vec::new(),
+        line_offset,
+        references: Vec::new(),
+    };
+    walker.template(
+        &ast,
+        &[Scope {
+            base: Some(String::new()),
+            params: Vec::new(),
+        }],
+    );
+
+    Ok(walker.references)
+}
+
+/// Whether the path exists in the config. A * segment requires the path to exist in every
+/// element, and is satisfied by an empty array or object
+pub fn is_defined(value: &Value, path: &str) -> bool {
+    let mut segments = path.splitn(2, '.');
+    let segment = match segments.next() {
+        Some(segment) if !segment.is_empty() => segment,
+        _ => return true,
+    };
+    let rest = segments.next().unwrap_or("");
+
+    match (value, segment) {
+        (Value::Array(list), ANY_ELEMENT) => list.iter().all(|v| is_defined(v, rest)),
+        (Value::Object(map), ANY_ELEMENT) => map.values().all(|v| is_defined(v, rest)),
+        (Value::Object(map), _) => map.get(segment).is_some_and(|v| is_defined(v, rest)),
+        (Value::Array(list), _) => segment
+            .parse::<usize>()
+            .ok()
+            .and_then(|i| list.get(i))
+            .is_some_and(|v| is_defined(v, rest)),
+        _ => false,
+    }
+}
+
+/// A referenced path and the environments which don't define it
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct ReferenceCheck {
+    #[serde(flatten)]
+    pub reference: Reference,
+    pub missing_from: Vec<String>,
+}
+
+impl ReferenceCheck {
+    /// Whether a strict render would fail for some environment because of this path
+    pub fn is_missing(&self) -> bool {
+        !self.reference.optional && !self.missing_from.is_empty()
+    }
+}
+
+/// Checks which of the environments define each referenced path. Paths under the reserved key are
+/// added when rendering, so are never missing
+pub fn check(references: Vec<Reference>, environments: &[Environment]) -> Vec<ReferenceCheck> {
+    references
+        .into_iter()
+        .map(|reference| ReferenceCheck {
+            missing_from: environments
+                .iter()
+                .filter(|e| {
+                    reference.path.split('.').next() != Some(RESERVED_KEY)
+                        && !is_defined(&e.config_data, &reference.path)
+                })
+                .map(|e| e.environment.clone())
+                .collect(),
+            reference,
+        })
+        .collect()
+}
+
+impl<'a, 'reg> Walker<'a, 'reg> {
+    fn template(&mut self, ast: &Ast, scopes: &[Scope]) {
+        for (index, element) in ast.elements.iter().enumerate() {
+            let position = ast
+                .mapping
+                .as_ref()
+                .and_then(|m| m.get(index))
+                .map(|TemplateMapping(line, column)| (*line, *column))
+                .unwrap_or((0, 0));
+            self.element(element, scopes, position);
+        }
+    }
+
+    fn element(&mut self, element: &TemplateElement, scopes: &[Scope], position: (usize, usize)) {
+        match element {
+            TemplateElement::HTMLExpression(param) => {
+                self.parameter(param, scopes, position, Usage::Value)
+            }
+            TemplateElement::Expression(helper) => {
+                let is_helper = !helper.params.is_empty()
+                    || !helper.hash.is_empty()
+                    || helper
+                        .name
+                        .as_name()
+                        .is_some_and(|n| self.handlebars.get_helper(n).is_some());
+                if is_helper {
+                    self.arguments(helper, scopes, position, Usage::Value);
+                } else {
+                    self.parameter(&helper.name, scopes, position, Usage::Value);
+                }
+            }
+            TemplateElement::HelperBlock(helper) => self.block(helper, scopes, position),
+            TemplateElement::PartialExpression(partial)
+            | TemplateElement::PartialBlock(partial) => {
+                for param in partial.params.iter().chain(partial.hash.values()) {
+                    self.parameter(param, scopes, position, Usage::Context);
+                }
+                // A context parameter renders the partial in a new block, @root is unchanged
+                let mut inner = scopes.to_vec();
+                if let Some(param) = partial.params.first() {
+                    inner.push(Scope {
+                        base: self.resolve(param, scopes),
+                        params: Vec::new(),
+                    });
+                }
+                if let Some(name) = partial.name.as_name() {
+                    self.partial(name, &inner);
+                }
+                if let Some(ref template) = partial.template {
+                    self.template(template, scopes);
+                }
+            }
+            TemplateElement::RawString(_)
+            | TemplateElement::Comment(_)
+            | TemplateElement::DecoratorExpression(_)
+            | TemplateElement::DecoratorBlock(_) => {}
+        }
+    }
+
+    fn block(&mut self, helper: &HelperTemplate, scopes: &[Scope], position: (usize, usize)) {
+        let name = helper.name.as_name().unwrap_or_default();
+        let context = helper.params.first().and_then(|p| self.resolve(p, scopes));
+        let inner = match name {
+            "each" | "comma-list" => Some(context.map(|c| join_path(&c, ANY_ELEMENT))),
+            "with" => Some(context),
+            _ => None,
+        };
+
+        match (name, helper.params.split_first()) {
+            ("if", _) | ("unless", _) => self.arguments(helper, scopes, position, Usage::Condition),
+            (_, Some((first, rest))) if inner.is_some() => {
+                self.parameter(first, scopes, position, Usage::Context);
+                for param in rest.iter().chain(helper.hash.values()) {
+                    self.parameter(param, scopes, position, Usage::Value);
+                }
+            }
+            _ => self.arguments(helper, scopes, position, Usage::Value),
+        }
+
+        if let Some(ref template) = helper.template {
+            match inner {
+                Some(base) => {
+                    let params = match helper.block_param {
+                        Some(BlockParam::Single(ref param)) => {
+                            vec![(block_param_name(param), base.clone())]
+                        }
+                        Some(BlockParam::Pair((ref value, ref key))) => vec![
+                            (block_param_name(value), base.clone()),
+                            (block_param_name(key), None),
+                        ],
+                        None => Vec::new(),
+                    };
+                    let mut scopes = scopes.to_vec();
+                    scopes.push(Scope { base, params });
+                    self.template(template, &scopes);
+                }
+                None => self.template(template, scopes),
+            }
+        }
+        if let Some(ref inverse) = helper.inverse {
+            self.template(inverse, scopes);
+        }
+    }
+
+    fn arguments(
+        &mut self,
+        helper: &HelperTemplate,
+        scopes: &[Scope],
+        position: (usize, usize),
+        usage: Usage,
+    ) {
+        for param in helper.params.iter().chain(helper.hash.values()) {
+            self.parameter(param, scopes, position, usage);
+        }
+    }
+
+    fn parameter(
+        &mut self,
+        param: &Parameter,
+        scopes: &[Scope],
+        position: (usize, usize),
+        usage: Usage,
+    ) {
+        match param {
+            Parameter::Subexpression(subexpression) => {
+                if let TemplateElement::Expression(ref helper) = *subexpression.element {
+                    self.arguments(helper, scopes, position, Usage::Value);
+                }
+            }
+            Parameter::Path(_) | Parameter::Name(_) => {
+                if let Some(path) = self.resolve(param, scopes).filter(|p| !p.is_empty()) {
+                    self.add(path, position, usage);
+                }
+            }
+            Parameter::Literal(_) => {}
+        }
+    }
+
+    fn partial(&mut self, name: &str, scopes: &[Scope]) {
+        let ast = match self.handlebars.get_template(name) {
+            Some(ast) if !self.partials.iter().any(|p| p == name) => ast,
+            _ => return,
+        };
+
+        self.partials.push(name.to_owned());
+        self.template(ast, scopes);
+        self.partials.pop();
+    }
+
+    fn add(&mut self, path: String, (line, column): (usize, usize), usage: Usage) {
+        if let Some(existing) = self.references.iter_mut().find(|r| r.path == path) {
+            existing.optional &= usage == Usage::Condition;
+            existing.value_used |= usage == Usage::Value;
+            return;
+        }
+
+        let partial = self.partials.last().cloned();
+        self.references.push(Reference {
+            line: if partial.is_none() {
+                line + self.line_offset
+            } else {
+                line
+            },
+            partial,
+            column,
+            path,
+            optional: usage == Usage::Condition,
+            value_used: usage == Usage::Value,
+        });
+    }
+
+    /// Resolves a path as written in the template to a config path, following ../, this, @root
+    /// and block parameters
+    fn resolve(&self, param: &Parameter, scopes: &[Scope]) -> Option<String> {
+        let raw = match param {
+            Parameter::Path(_) | Parameter::Name(_) => param.as_name()?,
+            _ => return None,
+        };
+
+        let (scope, raw) = if let Some(rest) = raw.strip_prefix("@root") {
+            (scopes.first()?, rest)
+        } else if raw.starts_with('@') {
+            return None;
+        } else {
+            let mut raw = raw;
+            let mut level = 0;
+            while let Some(rest) = raw.strip_prefix("../") {
+                raw = rest;
+                level += 1;
+            }
+            (scopes.get(scopes.len().checked_sub(level + 1)?)?, raw)
+        };
+
+        // Block parameters are only looked up for paths not explicitly relative to the context
+        let explicit = raw.starts_with('.') || raw.starts_with('/');
+        let mut segments = path_segments(raw);
+        let mut base = scope.base.clone();
+        if segments.first().is_some_and(|s| s == "this") {
+            segments.remove(0);
+        } else if let Some(first) = segments.first().filter(|_| !explicit) {
+            let param = scopes
+                .iter()
+                .rev()
+                .flat_map(|s| s.params.iter())
+                .find(|(name, _)| name == first);
+            if let Some((_, value)) = param {
+                base = value.clone();
+                segments.remove(0);
+            }
+        }
+
+        segments
+            .iter()
+            .try_fold(base?, |path, segment| Some(join_path(&path, segment)))
+    }
+}
+
+fn block_param_name(param: &Parameter) -> String {
+    param.as_name().unwrap_or_default().to_owned()
+}
+
+/// Splits a handlebars path into its segments, e.g. ./Servers.[0]/Endpoint => Servers, 0, Endpoint
+fn path_segments(raw: &str) -> Vec<String> {
+    let mut segments = Vec::new();
+    let mut segment = String::new();
+    let mut in_brackets = false;
+
+    for c in raw.chars() {
+        match c {
+            '[' if !in_brackets => in_brackets = true,
+            ']' if in_brackets => in_brackets = false,
+            '.' | '/' if !in_brackets => {
+                if !segment.is_empty() {
+                    segments.push(std::mem::take(&mut segment));
+                }
+            }
+            _ => segment.push(c),
+        }
+    }
+    if !segment.is_empty() {
+        segments.push(segment);
+    }
+
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+
+    fn template(source: &str) -> Template {
+        Template::new(
+            Path::new("templates"),
+            PathBuf::from("templates/Web.template.config"),
+            source,
+        )
+        .unwrap()
+    }
+
+    fn paths(handlebars: &Handlebars, source: &str) -> Vec<String> {
+        references(handlebars, &template(source))
+            .unwrap()
+            .into_iter()
+            .map(|r| r.path)
+            .collect()
+    }
+
+    #[test]
+    fn test_references() {
+        let mut handlebars = crate::transform::handlebars(false);
+        handlebars
+            .register_partial("logging", "{{Level}} {{@root.Region.Key}}")
+            .unwrap();
+
+        assert_eq!(
+            paths(
+                &handlebars,
+                "{{#if ConfigEnabled}}{{Region.Key}}{{/if}}\n\
+                 {{#comma-list Memcache.Servers}}{{Endpoint}}:{{this.Port}}{{/comma-list}}\n\
+                 {{#each Services as |service|}}{{service.[url]}} {{../Region.Name}} {{@index}}{{/each}}\n\
+                 {{#with DB}}{{Endpoint}}{{else}}{{Fallback}}{{/with}}\n\
+                 {{#if (eq (lowercase Env.Name) \"prod\")}}{{url-rm-slash Api.Url}}{{/if}}\n\
+                 {{> logging Logging}}"
+            ),
+            vec![
+                "ConfigEnabled",
+                "Region.Key",
+                "Memcache.Servers",
+                "Memcache.Servers.*.Endpoint",
+                "Memcache.Servers.*.Port",
+                "Services",
+                "Services.*.url",
+                "Region.Name",
+                "DB",
+                "DB.Endpoint",
+                "Fallback",
+                "Env.Name",
+                "Api.Url",
+                "Logging",
+                "Logging.Level",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_uses_render_metadata() {
+        let mut handlebars = crate::transform::handlebars(false);
+        handlebars
+            .register_partial("header", "{{@root.hogan.environment}}")
+            .unwrap();
+
+        assert!(uses_render_metadata(&handlebars, "{{hogan.sha}}"));
+        assert!(uses_render_metadata(
+            &handlebars,
+            "{{#each Items}}{{> header}}{{/each}}"
+        ));
+        assert!(!uses_render_metadata(
+            &handlebars,
+            "{{to-json this}}{{#each this}}{{@key}}{{/each}}"
+        ));
+        assert!(!uses_render_metadata(
+            &handlebars,
+            "{{#each Items}}{{hogan.sha}}{{/each}}"
+        ));
+        assert!(!uses_render_metadata(&handlebars, "{{#if}}"));
+    }
+
+    #[test]
+    fn test_reference_positions() {
+        let handlebars = crate::transform::handlebars(false);
+        let found = references(
+            &handlebars,
+            &template(
+                "---hogan\nmode: 0600\n---\n{{#if Optional}}\n  {{Required}}{{/if}}\n{{Optional}}",
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(
+            found,
+            vec![
+                Reference {
+                    path: "Optional".to_string(),
+                    partial: None,
+                    line: 4,
+                    column: 1,
+                    optional: false,
+                    value_used: true,
+                },
+                Reference {
+                    path: "Required".to_string(),
+                    partial: None,
+                    line: 5,
+                    column: 3,
+                    optional: false,
+                    value_used: true,
+                },
+            ]
+        );
+        assert!(
+            references(&handlebars, &template("{{#unless Flag}}x{{/unless}}")).unwrap()[0].optional
+        );
+        assert!(references(&handlebars, &template("{{#if}}")).is_err());
+    }
+
+    #[test]
+    fn test_is_defined() {
+        let config = serde_json::json!({
+            "Region": {"Key": "TEST", "Name": null},
+            "Servers": [{"Endpoint": "a", "Port": 1}, {"Endpoint": "b"}],
+            "Empty": [],
+        });
+
+        assert!(is_defined(&config, "Region.Key"));
+        assert!(is_defined(&config, "Region.Name"));
+        assert!(!is_defined(&config, "Region.Missing"));
+        assert!(!is_defined(&config, "Region.Key.Nested"));
+        assert!(is_defined(&config, "Servers.*.Endpoint"));
+        assert!(!is_defined(&config, "Servers.*.Port"));
+        assert!(is_defined(&config, "Servers.1.Endpoint"));
+        assert!(is_defined(&config, "Empty.*.Anything"));
+        assert!(is_defined(&config, "Region.*"));
+    }
+}